@@ -0,0 +1,390 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use super::profiles::{active_profile_env, get_profile};
+use super::project::{effective_jdk, JdkOrigin};
+use super::{cached_jdks, resolve_jdk};
+
+/// Profile variable names exported by the last `env --format csh`, space
+/// separated. csh shells have no other memory of them, so the next run reads
+/// this to unset variables the new profile no longer sets.
+pub const CSH_PROFILE_VARS_ENV_VAR: &str = "_JDK_PULSE_PROFILE_VARS";
+
+/// Output formats of `jdk-pulse env`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EnvFormat {
+    /// `export KEY='value'` (sh, bash, zsh)
+    Posix,
+    /// `set -gx KEY 'value'`
+    Fish,
+    /// `$env.KEY = "value"`
+    Nushell,
+    /// `$env:KEY = 'value'`
+    PowerShell,
+    /// `KEY=value`
+    Dotenv,
+    /// `{"KEY": "value"}`
+    Json,
+    /// `setenv KEY 'value';` (csh, tcsh)
+    Csh,
+}
+
+impl EnvFormat {
+    pub const ALL: &'static [EnvFormat] = &[
+        EnvFormat::Posix,
+        EnvFormat::Fish,
+        EnvFormat::Nushell,
+        EnvFormat::PowerShell,
+        EnvFormat::Dotenv,
+        EnvFormat::Json,
+        EnvFormat::Csh,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            EnvFormat::Posix => "posix",
+            EnvFormat::Fish => "fish",
+            EnvFormat::Nushell => "nushell",
+            EnvFormat::PowerShell => "powershell",
+            EnvFormat::Dotenv => "dotenv",
+            EnvFormat::Json => "json",
+            EnvFormat::Csh => "csh",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<EnvFormat> {
+        EnvFormat::ALL.iter().copied().find(|format| format.name() == name)
+    }
+}
+
+/// Environment that activates a JDK: `JAVA_HOME`, a rewritten `PATH` and any
+/// profile variables.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JdkEnvironment {
+    pub java_home: String,
+    /// PATH entries, new JDK `bin` first
+    pub path: Vec<String>,
+    /// Extra variables from a profile
+    pub env: BTreeMap<String, String>,
+    /// Profile variables of an earlier csh activation that `env` does not
+    /// set; the csh output unsets them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unset: Vec<String>,
+}
+
+impl JdkEnvironment {
+    /// All variables in output order: JAVA_HOME, PATH, then profile variables.
+    pub fn variables(&self) -> Vec<(String, String)> {
+        let mut variables = vec![
+            ("JAVA_HOME".to_string(), self.java_home.clone()),
+            ("PATH".to_string(), join_path(&self.path)),
+        ];
+        variables.extend(self.env.iter().map(|(k, v)| (k.clone(), v.clone())));
+        variables
+    }
+}
+
+/// Build the environment for `spec`, starting from the current process environment.
+///
+/// `spec` may be a profile name (its JDK and variables are used) or any JDK
+/// spec accepted by `resolve_jdk`. Without a spec, the effective JDK for `dir`
+/// is used (session, then project file, then global), together with the
+/// active profile's variables when the global selection applies.
+pub fn jdk_environment(spec: Option<&str>, dir: &Path) -> Result<JdkEnvironment, String> {
+    let (java_home, env) = match spec {
+        Some(spec) => match get_profile(spec) {
+            Ok(profile) => (resolve_jdk(&profile.jdk)?.home, profile.env),
+            Err(_) => (resolve_jdk(spec)?.home, BTreeMap::new()),
+        },
+        None => {
+            let effective = effective_jdk(dir)?.ok_or("No active JDK; pass a spec or select one first")?;
+            let env = if effective.origin == JdkOrigin::Global {
+                active_profile_env()
+            } else {
+                BTreeMap::new()
+            };
+            (effective.home, env)
+        }
+    };
+
+    // Every known JDK, plus the current JAVA_HOME in case discovery misses it
+    let mut jdk_homes: Vec<String> = cached_jdks()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|jdk| jdk.home)
+        .collect();
+    jdk_homes.extend(std::env::var("JAVA_HOME").ok().filter(|home| is_jdk_root(home)));
+    jdk_homes.push(java_home.clone());
+
+    let unset = std::env::var(CSH_PROFILE_VARS_ENV_VAR)
+        .unwrap_or_default()
+        .split_whitespace()
+        .filter(|name| !env.contains_key(*name) && *name != "JAVA_HOME" && *name != "PATH")
+        .map(|name| name.to_string())
+        .collect();
+
+    let current_path = std::env::var("PATH").unwrap_or_default();
+    Ok(JdkEnvironment {
+        path: rewrite_path(&split_path(&current_path), &bin_dir(&java_home), &jdk_homes),
+        java_home,
+        env,
+        unset,
+    })
+}
+
+/// Prefixes whose `bin` is never dropped, even when listed as a JDK home.
+const SYSTEM_PREFIXES: &[&str] = &["/", "/usr", "/usr/local"];
+
+/// Put `new_bin` first and drop the `bin` directories of `jdk_homes`
+/// (`<home>/bin` and `<home>/jre/bin`), so switching never piles up JDK
+/// directories. Other entries keep their order, even when they live under a
+/// home: a `JAVA_HOME` of `/usr` must not take `/usr/local/bin` with it, and
+/// system prefixes are skipped so it does not take `/usr/bin` either.
+pub fn rewrite_path(entries: &[String], new_bin: &str, jdk_homes: &[String]) -> Vec<String> {
+    let new_bin_path = Path::new(new_bin);
+    let homes: Vec<&String> = jdk_homes
+        .iter()
+        .filter(|home| !SYSTEM_PREFIXES.iter().any(|prefix| Path::new(home.as_str()) == Path::new(prefix)))
+        .collect();
+    let mut rewritten = vec![new_bin.to_string()];
+    rewritten.extend(
+        entries
+            .iter()
+            .filter(|entry| {
+                let entry = Path::new(entry.as_str());
+                entry != new_bin_path && !homes.iter().any(|home| is_jdk_bin_dir(entry, home))
+            })
+            .cloned(),
+    );
+    rewritten
+}
+
+/// Whether `home` is a JDK root: it has a `release` file, or `lib/modules`
+/// (JDK 9+ images without one). Discovery and a `JAVA_HOME` taken from the
+/// environment must pass this before their `bin` is dropped from `PATH`:
+/// distributions commonly set `JAVA_HOME` to `/usr`.
+pub(crate) fn is_jdk_root(home: &str) -> bool {
+    let home = Path::new(home);
+    !home.as_os_str().is_empty()
+        && (home.join("release").is_file() || home.join("lib").join("modules").is_file())
+}
+
+/// Whether `entry` is `<home>/bin` or `<home>/jre/bin`.
+pub(crate) fn is_jdk_bin_dir(entry: &Path, home: &str) -> bool {
+    let home = Path::new(home);
+    entry == home.join("bin") || entry == home.join("jre").join("bin")
+}
+
+/// Render the environment as code (or data) in the given format.
+pub fn render_environment(environment: &JdkEnvironment, format: EnvFormat) -> String {
+    let variables = environment.variables();
+    match format {
+        EnvFormat::Json => {
+            let map: BTreeMap<String, String> = variables.into_iter().collect();
+            format!("{}\n", serde_json::to_string_pretty(&map).unwrap_or_default())
+        }
+        _ => {
+            let mut out = String::new();
+            if format == EnvFormat::Csh {
+                for name in &environment.unset {
+                    out.push_str(&format!("unsetenv {};\n", name));
+                }
+            }
+            for (key, value) in &variables {
+                let line = match format {
+                    EnvFormat::Posix => format!("export {}={}", key, posix_quote(value)),
+                    EnvFormat::Fish if key == "PATH" => format!(
+                        "set -gx PATH {}",
+                        environment
+                            .path
+                            .iter()
+                            .map(|entry| fish_quote(entry))
+                            .collect::<Vec<_>>()
+                            .join(" ")
+                    ),
+                    EnvFormat::Fish => format!("set -gx {} {}", key, fish_quote(value)),
+                    EnvFormat::Nushell if key == "PATH" => format!(
+                        "$env.PATH = [{}]",
+                        environment
+                            .path
+                            .iter()
+                            .map(|entry| json_quote(entry))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                    EnvFormat::Nushell => format!("$env.{} = {}", key, json_quote(value)),
+                    EnvFormat::PowerShell => format!("$env:{} = '{}'", key, value.replace('\'', "''")),
+                    EnvFormat::Dotenv => format!("{}={}", key, dotenv_quote(value)),
+                    // `;` keeps the lines apart when evaluated through "`...`"
+                    EnvFormat::Csh => format!("setenv {} {};", key, csh_quote(value)),
+                    EnvFormat::Json => unreachable!(),
+                };
+                out.push_str(&line);
+                out.push('\n');
+            }
+            if format == EnvFormat::Csh {
+                let names: Vec<&str> = environment.env.keys().map(String::as_str).collect();
+                let line = if names.is_empty() {
+                    format!("unsetenv {};", CSH_PROFILE_VARS_ENV_VAR)
+                } else {
+                    format!("setenv {} {};", CSH_PROFILE_VARS_ENV_VAR, csh_quote(&names.join(" ")))
+                };
+                out.push_str(&line);
+                out.push('\n');
+            }
+            out
+        }
+    }
+}
+
+fn bin_dir(home: &str) -> String {
+    Path::new(home).join("bin").to_string_lossy().to_string()
+}
+
+fn split_path(path: &str) -> Vec<String> {
+    std::env::split_paths(path)
+        .map(|entry| entry.to_string_lossy().to_string())
+        .filter(|entry| !entry.is_empty())
+        .collect()
+}
+
+fn join_path(entries: &[String]) -> String {
+    std::env::join_paths(entries.iter().map(PathBuf::from))
+        .map(|joined| joined.to_string_lossy().to_string())
+        .unwrap_or_else(|_| entries.join(":"))
+}
+
+fn posix_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Single quotes, with `'` closed and escaped and `!` escaped against history expansion.
+fn csh_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''").replace('!', "\\!"))
+}
+
+fn fish_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+fn json_quote(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| format!("\"{}\"", value))
+}
+
+/// Leave simple values bare; double-quote values that dotenv parsers would split or strip.
+fn dotenv_quote(value: &str) -> String {
+    if value
+        .chars()
+        .any(|c| c.is_whitespace() || matches!(c, '#' | '"' | '\'' | '\\' | '$' | '`'))
+    {
+        format!(
+            "\"{}\"",
+            value.replace('\\', "\\\\").replace('"', "\\\"").replace('$', "\\$")
+        )
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_jdk_root, render_environment, rewrite_path, EnvFormat, JdkEnvironment};
+    use std::collections::BTreeMap;
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    #[test]
+    fn prepends_new_bin_and_keeps_other_entries_in_order() {
+        let path = strings(&["/usr/local/bin", "/usr/bin", "/bin"]);
+        let rewritten = rewrite_path(&path, "/opt/jdk-21/bin", &strings(&["/opt/jdk-21"]));
+        assert_eq!(rewritten, strings(&["/opt/jdk-21/bin", "/usr/local/bin", "/usr/bin", "/bin"]));
+    }
+
+    #[test]
+    fn removes_every_known_jdk_directory() {
+        let path = strings(&[
+            "/opt/jdk-17/bin",
+            "/usr/bin",
+            "/opt/jdk-11/bin",
+            "/opt/jdk-8/jre/bin",
+            "/opt/jdk-17/bin",
+            "/bin",
+        ]);
+        let homes = strings(&["/opt/jdk-8", "/opt/jdk-11", "/opt/jdk-17", "/opt/jdk-21"]);
+        let rewritten = rewrite_path(&path, "/opt/jdk-21/bin", &homes);
+        assert_eq!(rewritten, strings(&["/opt/jdk-21/bin", "/usr/bin", "/bin"]));
+    }
+
+    #[test]
+    fn moves_existing_new_bin_to_the_front_once() {
+        let path = strings(&["/usr/bin", "/opt/jdk-21/bin", "/bin", "/opt/jdk-21/bin/"]);
+        let rewritten = rewrite_path(&path, "/opt/jdk-21/bin", &strings(&["/opt/jdk-21"]));
+        assert_eq!(rewritten, strings(&["/opt/jdk-21/bin", "/usr/bin", "/bin"]));
+    }
+
+    #[test]
+    fn keeps_directories_that_only_share_a_name_prefix() {
+        let path = strings(&["/opt/jdk-17-tools/bin", "/opt/jdk-17/bin", "/usr/bin"]);
+        let rewritten = rewrite_path(&path, "/opt/jdk-21/bin", &strings(&["/opt/jdk-17"]));
+        assert_eq!(rewritten, strings(&["/opt/jdk-21/bin", "/opt/jdk-17-tools/bin", "/usr/bin"]));
+    }
+
+    #[test]
+    fn keeps_system_directories_when_a_home_is_a_system_prefix() {
+        let path = strings(&["/usr/local/bin", "/usr/bin", "/usr/lib/jvm/java-17/bin", "/bin"]);
+        let homes = strings(&["/usr", "/usr/lib/jvm/java-17"]);
+        let rewritten = rewrite_path(&path, "/opt/jdk-21/bin", &homes);
+        assert_eq!(
+            rewritten,
+            strings(&["/opt/jdk-21/bin", "/usr/local/bin", "/usr/bin", "/bin"])
+        );
+    }
+
+    #[test]
+    fn only_jdk_roots_count_as_homes() {
+        let dir = std::env::temp_dir().join(format!("jdk-pulse-root-{}", std::process::id()));
+        let modules = dir.join("modular");
+        std::fs::create_dir_all(modules.join("lib")).unwrap();
+        assert!(!is_jdk_root(&dir.to_string_lossy()));
+        std::fs::write(dir.join("release"), "JAVA_VERSION=\"21\"\n").unwrap();
+        assert!(is_jdk_root(&dir.to_string_lossy()));
+        assert!(!is_jdk_root(&modules.to_string_lossy()));
+        std::fs::write(modules.join("lib").join("modules"), "").unwrap();
+        assert!(is_jdk_root(&modules.to_string_lossy()));
+        assert!(!is_jdk_root(""));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn removes_unknown_new_bin_duplicates_without_touching_other_paths() {
+        let path = strings(&["/home/me/bin", "/custom/java/bin", "/usr/bin"]);
+        let rewritten = rewrite_path(&path, "/custom/java/bin", &[]);
+        assert_eq!(rewritten, strings(&["/custom/java/bin", "/home/me/bin", "/usr/bin"]));
+    }
+
+    #[test]
+    fn csh_output_unsets_variables_of_the_previous_profile() {
+        let environment = JdkEnvironment {
+            java_home: "/opt/jdk-21".to_string(),
+            path: strings(&["/opt/jdk-21/bin", "/usr/bin"]),
+            env: BTreeMap::from([("MAVEN_OPTS".to_string(), "-Xmx2g".to_string())]),
+            unset: strings(&["JAVA_TOOL_OPTIONS"]),
+        };
+        assert_eq!(
+            render_environment(&environment, EnvFormat::Csh),
+            "unsetenv JAVA_TOOL_OPTIONS;\n\
+             setenv JAVA_HOME '/opt/jdk-21';\n\
+             setenv PATH '/opt/jdk-21/bin:/usr/bin';\n\
+             setenv MAVEN_OPTS '-Xmx2g';\n\
+             setenv _JDK_PULSE_PROFILE_VARS 'MAVEN_OPTS';\n"
+        );
+
+        let without_profile = JdkEnvironment { env: BTreeMap::new(), ..environment };
+        let rendered = render_environment(&without_profile, EnvFormat::Csh);
+        assert!(rendered.ends_with("unsetenv _JDK_PULSE_PROFILE_VARS;\n"), "{}", rendered);
+    }
+}
@@ -0,0 +1,522 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Maximum number of local parent POMs followed (guards against cycles)
+const MAX_PARENT_DEPTH: usize = 10;
+
+/// Maximum nesting of `${...}` property references
+const MAX_INTERPOLATION_DEPTH: usize = 10;
+
+/// Maven properties that carry the Java version, highest priority first.
+/// `java.version` is the Spring Boot convention.
+const MAVEN_VERSION_PROPERTIES: &[&str] = &[
+    "maven.compiler.release",
+    "maven.compiler.target",
+    "maven.compiler.source",
+    "java.version",
+];
+
+/// Java version required by a Maven POM, e.g. `17` or `1.8`.
+///
+/// Looks at the `maven-compiler-plugin` configuration (`<release>`,
+/// `<target>`, `<source>`) and the `maven.compiler.*` / `java.version`
+/// properties of the top-level `<project>` (profiles are ignored). Parent POMs
+/// that exist on disk (`<relativePath>`, default `../pom.xml`) are inherited:
+/// the nearest POM that sets a version wins, and within one POM the compiler
+/// plugin wins over the properties.
+pub fn maven_java_version(pom: &Path) -> Option<String> {
+    chain_java_version(&load_pom_chain(pom))
+}
+
+/// `maven_java_version` for a POM chain, child first.
+fn chain_java_version(chain: &[String]) -> Option<String> {
+    let projects: Vec<&str> = chain.iter().filter_map(|xml| project_content(xml)).collect();
+    let levels: Vec<HashMap<String, String>> =
+        projects.iter().map(|project| maven_properties(project)).collect();
+
+    // Properties: the child overrides its parents
+    let mut properties = HashMap::new();
+    for level in levels.iter().rev() {
+        properties.extend(level.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+
+    let value = projects.iter().zip(&levels).find_map(|(project, level)| {
+        compiler_plugin_version(project).or_else(|| {
+            MAVEN_VERSION_PROPERTIES
+                .iter()
+                .find_map(|name| level.get(*name).cloned())
+        })
+    })?;
+
+    let value = interpolate(&value, &properties);
+    normalize_version(&value)
+}
+
+/// Java version required by a Gradle build script (Groovy or Kotlin DSL).
+///
+/// Recognises, in priority order: `JavaLanguageVersion.of(17)`,
+/// `jvmToolchain(17)`, `options.release = 17` / `options.release.set(17)`,
+/// `targetCompatibility` and `sourceCompatibility` (`17`, `'17'`, `1.8`,
+/// `JavaVersion.VERSION_17`, `JavaVersion.VERSION_1_8`).
+pub fn gradle_java_version(script: &str) -> Option<String> {
+    let script = strip_gradle_comments(script);
+
+    const MARKERS: &[&str] = &[
+        "JavaLanguageVersion.of",
+        "jvmToolchain",
+        "options.release",
+        "targetCompatibility",
+        "sourceCompatibility",
+    ];
+
+    MARKERS
+        .iter()
+        .find_map(|marker| version_after(&script, marker))
+        .and_then(|v| normalize_version(&v))
+}
+
+/// Read a POM and its local parents, child first.
+fn load_pom_chain(pom: &Path) -> Vec<String> {
+    let mut chain = Vec::new();
+    let mut next = Some(pom.to_path_buf());
+
+    while let Some(path) = next.take() {
+        if chain.len() >= MAX_PARENT_DEPTH {
+            break;
+        }
+        let xml = match fs::read_to_string(&path) {
+            Ok(xml) => strip_xml_comments(&xml),
+            Err(_) => break,
+        };
+        next = parent_pom_path(&path, &xml);
+        chain.push(xml);
+    }
+
+    chain
+}
+
+/// Location of the parent POM declared in `xml`, if it is a local file.
+fn parent_pom_path(pom: &Path, xml: &str) -> Option<PathBuf> {
+    let parent = child_content(project_content(xml)?, "parent")?;
+    let relative = if parent.contains("<relativePath/>") || parent.contains("<relativePath />") {
+        // An empty relativePath means "resolve from the repository only"
+        return None;
+    } else {
+        tag_content(parent, "relativePath")
+            .map(|s| s.trim())
+            .unwrap_or("../pom.xml")
+    };
+    if relative.is_empty() {
+        return None;
+    }
+
+    let mut path = pom.parent()?.join(relative);
+    if path.is_dir() {
+        path = path.join("pom.xml");
+    }
+    if path.is_file() {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+/// Body of the root `<project>` element.
+fn project_content(xml: &str) -> Option<&str> {
+    child_content(xml, "project")
+}
+
+/// Children of the `<properties>` element directly under `<project>`.
+fn maven_properties(project: &str) -> HashMap<String, String> {
+    let mut properties = HashMap::new();
+    let mut rest = match child_content(project, "properties") {
+        Some(block) => block,
+        None => return properties,
+    };
+
+    while let Some(start) = rest.find('<') {
+        let after = &rest[start + 1..];
+        let end = match after.find('>') {
+            Some(end) => end,
+            None => break,
+        };
+        let name = &after[..end];
+        if name.starts_with('/') || name.ends_with('/') {
+            rest = &after[end + 1..];
+            continue;
+        }
+        let body = &after[end + 1..];
+        let close = format!("</{}>", name);
+        match body.find(&close) {
+            Some(close_at) => {
+                properties.insert(name.to_string(), body[..close_at].trim().to_string());
+                rest = &body[close_at + close.len()..];
+            }
+            None => break,
+        }
+    }
+
+    properties
+}
+
+/// `<release>`, `<target>` or `<source>` from the maven-compiler-plugin
+/// configuration in `<project><build>` (including `<pluginManagement>`).
+fn compiler_plugin_version(project: &str) -> Option<String> {
+    let mut rest = child_content(project, "build")?;
+    while let Some(plugin) = tag_content(rest, "plugin") {
+        // `plugin` is a slice of `rest`; continue after it
+        let plugin_end = plugin.as_ptr() as usize - rest.as_ptr() as usize + plugin.len();
+        if child_content(plugin, "artifactId").map(|s| s.trim()) == Some("maven-compiler-plugin") {
+            if let Some(configuration) = child_content(plugin, "configuration") {
+                for tag in ["release", "target", "source"] {
+                    if let Some(value) = child_content(configuration, tag) {
+                        return Some(value.trim().to_string());
+                    }
+                }
+            }
+        }
+        rest = &rest[plugin_end..];
+    }
+    None
+}
+
+/// Resolve `${name}` references against the POM properties.
+fn interpolate(value: &str, properties: &HashMap<String, String>) -> String {
+    let mut value = value.to_string();
+    for _ in 0..MAX_INTERPOLATION_DEPTH {
+        let start = match value.find("${") {
+            Some(start) => start,
+            None => break,
+        };
+        let end = match value[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+        let replacement = match properties.get(&value[start + 2..end]) {
+            Some(replacement) => replacement.clone(),
+            None => break,
+        };
+        value.replace_range(start..=end, &replacement);
+    }
+    value
+}
+
+/// Content between the first `<tag>` (or `<tag attr=...>`) and its closing tag.
+fn tag_content<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    let open = format!("<{}", tag);
+    let mut search_from = 0;
+    loop {
+        let start = search_from + xml[search_from..].find(&open)?;
+        let after_name = &xml[start + open.len()..];
+        // Make sure we matched the whole tag name (`<plugin>`, not `<plugins>`)
+        match after_name.chars().next() {
+            Some('>') | Some(' ') | Some('\t') | Some('\n') | Some('\r') => {}
+            _ => {
+                search_from = start + open.len();
+                continue;
+            }
+        }
+        let body_start = start + open.len() + after_name.find('>')? + 1;
+        let close = format!("</{}>", tag);
+        let body_end = body_start + xml[body_start..].find(&close)?;
+        return Some(&xml[body_start..body_end]);
+    }
+}
+
+/// Content of the first `<tag>` element that is a direct child of `xml`,
+/// skipping elements of the same name nested deeper (e.g. inside `<profiles>`).
+fn child_content<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    let mut depth = 0usize;
+    let mut body_start = None;
+    let mut pos = 0;
+    while let Some(found) = xml[pos..].find('<') {
+        let start = pos + found;
+        let end = start + xml[start..].find('>')?;
+        let inner = &xml[start + 1..end];
+        pos = end + 1;
+
+        if inner.starts_with('?') || inner.starts_with('!') || inner.ends_with('/') {
+            continue;
+        }
+        if let Some(name) = inner.strip_prefix('/') {
+            depth = depth.checked_sub(1)?;
+            if depth == 0 && name.trim() == tag {
+                if let Some(body_start) = body_start {
+                    return Some(&xml[body_start..start]);
+                }
+            }
+            continue;
+        }
+        let name = inner.split(char::is_whitespace).next().unwrap_or_default();
+        if depth == 0 && name == tag {
+            body_start = Some(pos);
+        }
+        depth += 1;
+    }
+    None
+}
+
+fn strip_xml_comments(xml: &str) -> String {
+    let mut result = String::with_capacity(xml.len());
+    let mut rest = xml;
+    while let Some(start) = rest.find("<!--") {
+        result.push_str(&rest[..start]);
+        match rest[start..].find("-->") {
+            Some(end) => rest = &rest[start + end + 3..],
+            None => return result,
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Drop `/* ... */` blocks and whole-line `//` comments. Trailing `//`
+/// comments are kept so that URLs in strings survive.
+fn strip_gradle_comments(script: &str) -> String {
+    let mut without_blocks = String::with_capacity(script.len());
+    let mut rest = script;
+    while let Some(start) = rest.find("/*") {
+        without_blocks.push_str(&rest[..start]);
+        match rest[start..].find("*/") {
+            Some(end) => rest = &rest[start + end + 2..],
+            None => {
+                rest = "";
+                break;
+            }
+        }
+    }
+    without_blocks.push_str(rest);
+
+    without_blocks
+        .lines()
+        .filter(|line| !line.trim_start().starts_with("//"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The version literal following `marker`, skipping `=`, `(`, `.set(`,
+/// quotes, whitespace and a `JavaVersion.VERSION_` / `JavaVersion.toVersion(` /
+/// `JavaLanguageVersion.of(` prefix.
+fn version_after(script: &str, marker: &str) -> Option<String> {
+    let mut search_from = 0;
+    while let Some(found) = script[search_from..].find(marker) {
+        let start = search_from + found + marker.len();
+        search_from = start;
+
+        let mut rest = script[start..]
+            .trim_start_matches(|c: char| c.is_whitespace() || c == '=' || c == '(');
+        if let Some(stripped) = rest.strip_prefix(".set(") {
+            rest = stripped;
+        }
+        for prefix in ["JavaVersion.VERSION_", "JavaVersion.toVersion(", "JavaLanguageVersion.of("] {
+            if let Some(stripped) = rest.strip_prefix(prefix) {
+                rest = stripped;
+            }
+        }
+        let rest = rest.trim_start_matches(['"', '\'']);
+
+        let literal: String = rest
+            .chars()
+            .take_while(|c| c.is_ascii_digit() || *c == '.' || *c == '_')
+            .collect();
+        if !literal.is_empty() {
+            return Some(literal.replace('_', "."));
+        }
+    }
+    None
+}
+
+/// Accept only plain version numbers (`17`, `1.8`, `21.0.1`).
+fn normalize_version(value: &str) -> Option<String> {
+    let value = value.trim().trim_end_matches('.');
+    if !value.is_empty()
+        && value.starts_with(|c: char| c.is_ascii_digit())
+        && value.chars().all(|c| c.is_ascii_digit() || c == '.')
+    {
+        Some(value.to_string())
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{chain_java_version, gradle_java_version};
+
+    fn pom(body: &str) -> String {
+        format!("<?xml version=\"1.0\"?>\n<project>\n{}\n</project>\n", body)
+    }
+
+    fn compiler_plugin(configuration: &str) -> String {
+        format!(
+            "<build><plugins><plugin><groupId>org.apache.maven.plugins</groupId>\
+             <artifactId>maven-compiler-plugin</artifactId>\
+             <configuration>{}</configuration></plugin></plugins></build>",
+            configuration
+        )
+    }
+
+    #[test]
+    fn maven_versions() {
+        let cases: &[(&str, Vec<String>, Option<&str>)] = &[
+            (
+                "release property",
+                vec![pom("<properties><maven.compiler.release>17</maven.compiler.release></properties>")],
+                Some("17"),
+            ),
+            (
+                "release beats target",
+                vec![pom(
+                    "<properties><maven.compiler.target>11</maven.compiler.target>\
+                     <maven.compiler.release>17</maven.compiler.release></properties>",
+                )],
+                Some("17"),
+            ),
+            (
+                "spring boot java.version",
+                vec![pom("<properties><java.version>1.8</java.version></properties>")],
+                Some("1.8"),
+            ),
+            (
+                "compiler plugin beats properties in the same pom",
+                vec![pom(&format!(
+                    "<properties><java.version>11</java.version></properties>{}",
+                    compiler_plugin("<release>21</release>")
+                ))],
+                Some("21"),
+            ),
+            (
+                "plugin value interpolated from properties",
+                vec![pom(&format!(
+                    "<properties><jdk>17</jdk></properties>{}",
+                    compiler_plugin("<release>${jdk}</release>")
+                ))],
+                Some("17"),
+            ),
+            (
+                "profile properties ignored",
+                vec![pom(
+                    "<profiles><profile><id>legacy</id><properties>\
+                     <maven.compiler.release>8</maven.compiler.release>\
+                     </properties></profile></profiles>\
+                     <properties><maven.compiler.release>21</maven.compiler.release></properties>",
+                )],
+                Some("21"),
+            ),
+            (
+                "only profile properties",
+                vec![pom(
+                    "<profiles><profile><properties><java.version>8</java.version>\
+                     </properties></profile></profiles>",
+                )],
+                None,
+            ),
+            (
+                "child property overrides parent plugin literal",
+                vec![
+                    pom("<properties><maven.compiler.release>21</maven.compiler.release></properties>"),
+                    pom(&compiler_plugin("<release>11</release>")),
+                ],
+                Some("21"),
+            ),
+            (
+                "child overrides parent property",
+                vec![
+                    pom("<properties><java.version>17</java.version></properties>"),
+                    pom("<properties><java.version>11</java.version></properties>"),
+                ],
+                Some("17"),
+            ),
+            (
+                "parent plugin uses child property",
+                vec![
+                    pom("<properties><jdk>21</jdk></properties>"),
+                    pom(&format!(
+                        "<properties><jdk>11</jdk></properties>{}",
+                        compiler_plugin("<release>${jdk}</release>")
+                    )),
+                ],
+                Some("21"),
+            ),
+            (
+                "inherited from parent",
+                vec![
+                    pom("<artifactId>module</artifactId>"),
+                    pom("<properties><maven.compiler.source>11</maven.compiler.source></properties>"),
+                ],
+                Some("11"),
+            ),
+            (
+                "unresolved property reference",
+                vec![pom("<properties><java.version>${unknown}</java.version></properties>")],
+                None,
+            ),
+        ];
+
+        for (name, chain, expected) in cases {
+            assert_eq!(chain_java_version(chain).as_deref(), *expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn gradle_versions() {
+        let cases: &[(&str, &str, Option<&str>)] = &[
+            (
+                "groovy toolchain",
+                "java {\n    toolchain {\n        languageVersion = JavaLanguageVersion.of(17)\n    }\n}\n",
+                Some("17"),
+            ),
+            (
+                "kts toolchain",
+                "java {\n    toolchain {\n        languageVersion.set(JavaLanguageVersion.of(21))\n    }\n}\n",
+                Some("21"),
+            ),
+            ("kotlin jvmToolchain", "kotlin {\n    jvmToolchain(17)\n}\n", Some("17")),
+            (
+                "toolchain beats sourceCompatibility",
+                "sourceCompatibility = '1.8'\njava { toolchain { languageVersion = JavaLanguageVersion.of(11) } }\n",
+                Some("11"),
+            ),
+            (
+                "options.release assignment",
+                "tasks.withType(JavaCompile) {\n    options.release = 11\n}\n",
+                Some("11"),
+            ),
+            (
+                "options.release.set",
+                "tasks.withType<JavaCompile> {\n    options.release.set(17)\n}\n",
+                Some("17"),
+            ),
+            (
+                "unrelated release assignment",
+                "val release = \"2.1.0\"\nversion = release\n",
+                None,
+            ),
+            (
+                "unrelated release before sourceCompatibility",
+                "def release = 3\nsourceCompatibility = JavaVersion.VERSION_11\n",
+                Some("11"),
+            ),
+            (
+                "targetCompatibility beats sourceCompatibility",
+                "sourceCompatibility = JavaVersion.VERSION_1_8\ntargetCompatibility = JavaVersion.VERSION_11\n",
+                Some("11"),
+            ),
+            (
+                "JavaVersion underscore form",
+                "java {\n    sourceCompatibility = JavaVersion.VERSION_1_8\n}\n",
+                Some("1.8"),
+            ),
+            (
+                "commented out",
+                "// sourceCompatibility = 17\n/* jvmToolchain(21) */\n",
+                None,
+            ),
+        ];
+
+        for (name, script, expected) in cases {
+            assert_eq!(gradle_java_version(script).as_deref(), *expected, "{}", name);
+        }
+    }
+}
@@ -0,0 +1,248 @@
+use std::collections::BTreeSet;
+
+use super::activation::EnvFormat;
+use super::cached_jdks;
+use super::config::load_config_or_default;
+use super::hooks::Shell;
+use super::profiles::list_profiles;
+
+/// Top-level CLI commands offered by completion.
+const COMMANDS: &[&str] = &[
+    "--list", "--set", "--get", "use", "back", "forward", "history", "doctor", "ps", "daemons", "profile", "session", "project", "home",
+    "env", "exec", "shell", "upgrade", "desktop", "multiplexers", "direnv", "shims", "config", "completions",
+];
+
+/// Where the candidates for one argument position come from.
+#[derive(Clone, Copy)]
+enum Source {
+    Words(&'static [&'static str]),
+    /// Output of `jdk-pulse __complete <kind>`
+    Dynamic(&'static str),
+    /// Formats of `env --format`
+    Formats,
+    /// Shells with a hook generator
+    Shells,
+    Directories,
+}
+
+/// Candidates for the argument that follows `args`, a space-separated pattern
+/// glob of the preceding arguments (`*` matches any text).
+struct Rule {
+    args: &'static [&'static str],
+    /// Also match any number of further arguments (e.g. `shell install zsh fish`)
+    repeat: bool,
+    sources: &'static [Source],
+}
+
+const RULES: &[Rule] = &[
+    Rule { args: &[""], repeat: false, sources: &[Source::Words(COMMANDS)] },
+    Rule { args: &["--set", "-s", "use"], repeat: false, sources: &[Source::Dynamic("specs")] },
+    Rule { args: &["env --format", "env -f"], repeat: false, sources: &[Source::Formats] },
+    Rule { args: &["env --format *", "env -f *"], repeat: false, sources: &[Source::Dynamic("targets")] },
+    Rule { args: &["env"], repeat: false, sources: &[Source::Words(&["--format"]), Source::Dynamic("targets")] },
+    Rule { args: &["exec"], repeat: false, sources: &[Source::Dynamic("targets")] },
+    Rule { args: &["exec *"], repeat: false, sources: &[Source::Words(&["--"])] },
+    Rule { args: &["profile"], repeat: false, sources: &[Source::Words(&["list", "use", "add", "remove"])] },
+    Rule { args: &["profile use", "profile remove"], repeat: false, sources: &[Source::Dynamic("profiles")] },
+    Rule { args: &["profile add *"], repeat: false, sources: &[Source::Dynamic("specs")] },
+    Rule { args: &["session"], repeat: false, sources: &[Source::Words(&["show", "set", "clear"])] },
+    Rule { args: &["session set"], repeat: false, sources: &[Source::Dynamic("specs")] },
+    Rule { args: &["shell"], repeat: false, sources: &[Source::Words(&["install", "remove", "print"])] },
+    Rule { args: &["shell install", "shell remove"], repeat: true, sources: &[Source::Shells] },
+    Rule { args: &["shell print"], repeat: false, sources: &[Source::Shells] },
+    Rule { args: &["upgrade"], repeat: false, sources: &[Source::Words(&["--force"])] },
+    Rule { args: &["config"], repeat: false, sources: &[Source::Words(&["show", "check", "path"])] },
+    Rule { args: &["daemons"], repeat: false, sources: &[Source::Words(&["stop"])] },
+    Rule { args: &["desktop"], repeat: false, sources: &[Source::Words(&["sync", "remove"])] },
+    Rule { args: &["multiplexers"], repeat: false, sources: &[Source::Words(&["sync"])] },
+    Rule { args: &["direnv"], repeat: false, sources: &[Source::Words(&["install", "remove", "print"])] },
+    Rule { args: &["shims"], repeat: false, sources: &[Source::Words(&["install", "remove", "path"])] },
+    Rule { args: &["completions"], repeat: false, sources: &[Source::Words(&["bash", "zsh", "fish"])] },
+    Rule { args: &["project", "home --project"], repeat: false, sources: &[Source::Directories] },
+    Rule { args: &["home"], repeat: false, sources: &[Source::Words(&["--project"]), Source::Directories] },
+];
+
+/// Values for `jdk-pulse __complete <kind>`, read from the discovery cache so
+/// completion stays fast.
+///
+/// - `specs`: JDK IDs, major versions and aliases
+/// - `profiles`: profile names
+/// - `targets`: specs and profile names (for `env` and `exec`)
+pub fn completion_candidates(kind: &str) -> Result<Vec<String>, String> {
+    let mut candidates = BTreeSet::new();
+    if matches!(kind, "specs" | "targets") {
+        for jdk in cached_jdks()? {
+            candidates.insert(jdk.id);
+            candidates.insert(jdk.version_major.to_string());
+        }
+        candidates.extend(load_config_or_default().aliases.into_keys());
+    }
+    if matches!(kind, "profiles" | "targets") {
+        candidates.extend(list_profiles()?.into_iter().map(|profile| profile.name));
+    }
+    if !matches!(kind, "specs" | "profiles" | "targets") {
+        return Err(format!("Unknown completion kind: {}", kind));
+    }
+    Ok(candidates.into_iter().collect())
+}
+
+/// Completion script for `shell`, registered for the `jdk-pulse` command.
+pub fn generate_completions(shell: Shell) -> Result<String, String> {
+    match shell {
+        Shell::Bash => Ok(bash_completions()),
+        Shell::Zsh => Ok(zsh_completions()),
+        Shell::Fish => Ok(fish_completions()),
+        other => Err(format!(
+            "Completions are not available for {} (supported: bash, zsh, fish)",
+            other.name()
+        )),
+    }
+}
+
+fn static_words(source: Source) -> Option<Vec<&'static str>> {
+    match source {
+        Source::Words(words) => Some(words.to_vec()),
+        Source::Formats => Some(EnvFormat::ALL.iter().map(|format| format.name()).collect()),
+        Source::Shells => Some(Shell::ALL.iter().map(|shell| shell.name()).collect()),
+        Source::Dynamic(_) | Source::Directories => None,
+    }
+}
+
+/// `case` patterns (bash and zsh) for one rule: literal text quoted, `*` left as a glob.
+fn case_patterns(rule: &Rule) -> String {
+    let glob = |args: &str| {
+        let pattern = args
+            .split('*')
+            .map(|part| if part.is_empty() { String::new() } else { format!("\"{}\"", part) })
+            .collect::<Vec<_>>()
+            .join("*");
+        if pattern.is_empty() {
+            "\"\"".to_string()
+        } else {
+            pattern
+        }
+    };
+    let mut patterns = Vec::new();
+    for args in rule.args {
+        patterns.push(glob(args));
+        if rule.repeat {
+            patterns.push(glob(&format!("{} *", args)));
+        }
+    }
+    patterns.join("|")
+}
+
+fn bash_completions() -> String {
+    let mut cases = String::new();
+    for rule in RULES {
+        let mut body = Vec::new();
+        for &source in rule.sources {
+            match source {
+                Source::Dynamic(kind) => body.push(format!(
+                    "words+=\" $(\"${{COMP_WORDS[0]}}\" __complete {} 2>/dev/null)\"",
+                    kind
+                )),
+                Source::Directories => body.push("dirs=1".to_string()),
+                source => body.push(format!("words+=\" {}\"", static_words(source).unwrap_or_default().join(" "))),
+            }
+        }
+        cases.push_str(&format!("    {}) {} ;;\n", case_patterns(rule), body.join("; ")));
+    }
+
+    format!(
+        r#"# bash completion for jdk-pulse
+_jdk_pulse() {{
+  local cur="${{COMP_WORDS[COMP_CWORD]}}" words="" dirs=0
+  local args="${{COMP_WORDS[*]:1:COMP_CWORD-1}}"
+  case "$args" in
+{cases}  esac
+  COMPREPLY=($(compgen -W "$words" -- "$cur"))
+  if [ "$dirs" = 1 ]; then
+    COMPREPLY+=($(compgen -d -- "$cur"))
+  fi
+}}
+complete -o default -F _jdk_pulse jdk-pulse
+"#
+    )
+}
+
+fn zsh_completions() -> String {
+    let mut cases = String::new();
+    for rule in RULES {
+        let mut body = Vec::new();
+        for &source in rule.sources {
+            match source {
+                Source::Dynamic(kind) => body.push(format!(
+                    "compadd -- ${{(f)\"$(\"${{words[1]}}\" __complete {} 2>/dev/null)\"}}",
+                    kind
+                )),
+                Source::Directories => body.push("_directories".to_string()),
+                source => body.push(format!("compadd -- {}", static_words(source).unwrap_or_default().join(" "))),
+            }
+        }
+        cases.push_str(&format!("    {}) {} ;;\n", case_patterns(rule), body.join("; ")));
+    }
+
+    format!(
+        r#"#compdef jdk-pulse
+# zsh completion for jdk-pulse: put in $fpath as _jdk-pulse, or source it
+_jdk_pulse() {{
+  local args="${{(j: :)words[2,CURRENT-1]}}"
+  case "$args" in
+    "exec "*" --"|"exec "*" -- "*)
+      shift 4 words
+      (( CURRENT -= 4 ))
+      _normal
+      ;;
+{cases}  esac
+}}
+if [ "$funcstack[1]" = "_jdk_pulse" ]; then
+  _jdk_pulse "$@"
+else
+  compdef _jdk_pulse jdk-pulse
+fi
+"#
+    )
+}
+
+fn fish_completions() -> String {
+    let mut lines = Vec::new();
+    for rule in RULES {
+        let mut patterns = Vec::new();
+        for args in rule.args {
+            patterns.push(format!("'{}'", args));
+            if rule.repeat {
+                patterns.push(format!("'{} *'", args));
+            }
+        }
+        let condition = format!("__jdk_pulse_args {}", patterns.join(" "));
+        for &source in rule.sources {
+            let candidates = match source {
+                Source::Dynamic(kind) => format!("(__jdk_pulse_complete {})", kind),
+                Source::Directories => "(__fish_complete_directories)".to_string(),
+                source => static_words(source).unwrap_or_default().join(" "),
+            };
+            lines.push(format!("complete -c jdk-pulse -n \"{}\" -a \"{}\"", condition, candidates));
+        }
+    }
+
+    format!(
+        r#"# fish completion for jdk-pulse
+function __jdk_pulse_args --description 'Test the arguments before the cursor against glob patterns'
+    set -l tokens (commandline -opc)
+    set -l args (string join ' ' -- $tokens[2..-1])
+    for pattern in $argv
+        string match -q -- $pattern "$args"; and return 0
+    end
+    return 1
+end
+function __jdk_pulse_complete --description 'Dynamic jdk-pulse candidates'
+    set -l cli (commandline -opc)[1]
+    command $cli __complete $argv 2>/dev/null
+end
+complete -c jdk-pulse -f
+{}
+"#,
+        lines.join("\n")
+    )
+}
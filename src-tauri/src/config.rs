@@ -0,0 +1,186 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use super::expand_home;
+use super::hooks::Shell;
+use super::ide::KNOWN_IDE_INTEGRATIONS;
+
+/// User preferences from `config.toml` in the app config directory
+/// (e.g. `~/.config/jdk-pulse/config.toml`).
+///
+/// ```toml
+/// preferred_vendors = ["temurin", "corretto"]
+/// hidden = ["java-1_8_0_292"]
+/// scan_roots = ["~/.sdkman/candidates/java", "/opt/jdks"]
+///
+/// [aliases]
+/// work = "/opt/jdks/corretto-17"
+/// legacy = "8"
+///
+/// [integrations]
+/// shells = ["zsh", "bash"]
+/// ides = ["vscode"]
+/// desktop = true
+/// multiplexers = true
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Vendors preferred when a spec matches several JDKs, most preferred first
+    pub preferred_vendors: Vec<String>,
+    /// Alias name -> JDK spec (ID, home path or version)
+    pub aliases: BTreeMap<String, String>,
+    /// JDK IDs left out of discovery, the tray and the CLI
+    pub hidden: Vec<String>,
+    /// Extra directories scanned for JDK installations
+    pub scan_roots: Vec<String>,
+    pub integrations: Integrations,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Integrations {
+    /// Shells whose hooks are installed by default
+    pub shells: Vec<String>,
+    /// IDEs whose JDK setting follows each switch (see `KNOWN_IDE_INTEGRATIONS`)
+    #[serde(default)]
+    pub ides: Vec<String>,
+    /// Publish each switch to desktop-launched apps (environment.d and the systemd user manager)
+    pub desktop: bool,
+    /// Update the global environment of running tmux servers and screen sessions on each switch
+    pub multiplexers: bool,
+}
+
+impl Default for Integrations {
+    fn default() -> Self {
+        Integrations {
+            shells: vec!["zsh".to_string(), "bash".to_string()],
+            ides: Vec::new(),
+            desktop: false,
+            multiplexers: false,
+        }
+    }
+}
+
+impl Config {
+    /// Follow an alias to its JDK spec; other specs are returned unchanged.
+    pub fn resolve_alias<'a>(&'a self, spec: &'a str) -> &'a str {
+        self.aliases.get(spec).map(|s| s.as_str()).unwrap_or(spec)
+    }
+
+    /// Aliases pointing at the given JDK ID or home.
+    pub fn aliases_for(&self, id: &str, home: &str) -> Vec<String> {
+        self.aliases
+            .iter()
+            .filter(|(_, target)| {
+                let target = target.as_str();
+                target == id || target == home || expand_home(target).to_string_lossy() == home
+            })
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    pub fn scan_root_paths(&self) -> Vec<PathBuf> {
+        self.scan_roots.iter().map(|root| expand_home(root)).collect()
+    }
+}
+
+/// Load the config file. A missing file yields the defaults.
+pub fn load_config() -> Result<Config, String> {
+    let path = get_config_file_path();
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(_) => return Ok(Config::default()),
+    };
+
+    toml::from_str(&contents)
+        .map_err(|e| format!("Failed to parse config file {}: {e}", path.display()))
+}
+
+/// Load the config, falling back to the defaults (with a warning) if it is invalid,
+/// so a typo in the config never breaks switching.
+pub fn load_config_or_default() -> Config {
+    match load_config() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Warning: {e}");
+            Config::default()
+        }
+    }
+}
+
+/// Check a config for mistakes the parser cannot catch. Returns one message per problem.
+pub fn validate_config(config: &Config) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    for vendor in &config.preferred_vendors {
+        if vendor.trim().is_empty() {
+            problems.push("preferred_vendors contains an empty entry".to_string());
+        }
+    }
+
+    for (name, target) in &config.aliases {
+        let valid_name = !name.is_empty()
+            && !name.starts_with(|c: char| c.is_ascii_digit())
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.');
+        if !valid_name {
+            problems.push(format!(
+                "alias '{}' must start with a letter and use only letters, digits, '-', '_' or '.'",
+                name
+            ));
+        }
+        if target.trim().is_empty() {
+            problems.push(format!("alias '{}' has an empty target", name));
+        } else if config.aliases.contains_key(target.as_str()) {
+            problems.push(format!("alias '{}' points at another alias ('{}')", name, target));
+        } else if (target.starts_with('/') || target.starts_with("~/")) && !expand_home(target).is_dir() {
+            problems.push(format!("alias '{}' points at a missing directory: {}", name, target));
+        }
+    }
+
+    for id in &config.hidden {
+        if id.trim().is_empty() {
+            problems.push("hidden contains an empty JDK ID".to_string());
+        }
+    }
+
+    for (root, path) in config.scan_roots.iter().zip(config.scan_root_paths()) {
+        if !path.is_dir() {
+            problems.push(format!("scan root does not exist: {}", root));
+        }
+    }
+
+    for shell in &config.integrations.shells {
+        if Shell::from_name(shell).is_none() {
+            let known: Vec<&str> = Shell::ALL.iter().map(|s| s.name()).collect();
+            problems.push(format!(
+                "unknown shell integration '{}' (known: {})",
+                shell,
+                known.join(", ")
+            ));
+        }
+    }
+    for ide in &config.integrations.ides {
+        if !KNOWN_IDE_INTEGRATIONS.contains(&ide.as_str()) {
+            problems.push(format!(
+                "unknown IDE integration '{}' (known: {})",
+                ide,
+                KNOWN_IDE_INTEGRATIONS.join(", ")
+            ));
+        }
+    }
+
+    problems
+}
+
+/// `config.toml` in the app config directory, e.g. `~/.config/jdk-pulse/config.toml`.
+pub fn get_config_file_path() -> PathBuf {
+    match dirs::config_dir() {
+        Some(dir) => dir.join("jdk-pulse").join("config.toml"),
+        None => PathBuf::from("config.toml"),
+    }
+}
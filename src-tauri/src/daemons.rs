@@ -0,0 +1,330 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use super::hsperf::list_jvms;
+use super::processes::{command_line, expected_home, same_jdk, user_processes, ProcessEntry, ProcessKind};
+use super::{cached_jdks, get_state_file_path};
+
+/// How long a daemon gets to exit after SIGTERM before it is killed
+const STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Build and language-server daemons that outlive a JDK switch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DaemonKind {
+    Gradle,
+    /// Kotlin compile daemon
+    Kotlin,
+    /// Maven daemon
+    Mvnd,
+    /// Eclipse JDT language server (VS Code Java, editors using jdtls)
+    Jdtls,
+}
+
+impl DaemonKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DaemonKind::Gradle => "Gradle daemon",
+            DaemonKind::Kotlin => "Kotlin daemon",
+            DaemonKind::Mvnd => "Maven daemon",
+            DaemonKind::Jdtls => "jdtls",
+        }
+    }
+}
+
+/// A running daemon and the JDK it runs on.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JvmDaemon {
+    pub pid: u32,
+    pub kind: DaemonKind,
+    pub java_home: Option<String>,
+    pub version_major: Option<u32>,
+    /// JDK it should run on: its session or project pin, else `~/.jdk_current`
+    pub expected_home: Option<String>,
+    /// Whether `java_home` is `expected_home`; None when either is unknown
+    pub on_expected_jdk: Option<bool>,
+    pub command: String,
+}
+
+impl JvmDaemon {
+    /// On a known JDK other than the one that applies to it. Daemons whose
+    /// JDK is unknown are never stale, so they are never stopped.
+    pub fn is_stale(&self) -> bool {
+        self.on_expected_jdk == Some(false)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StopOutcome {
+    /// Exited after SIGTERM
+    Terminated,
+    /// Still running after `STOP_TIMEOUT`, so SIGKILL was sent
+    Killed,
+    Failed,
+    /// Left running because its JDK is unknown
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaemonStop {
+    pub daemon: JvmDaemon,
+    pub outcome: StopOutcome,
+}
+
+/// Gradle, Kotlin, mvnd and jdtls daemons of the current user, recognised by
+/// their main class. JVMs come from hsperfdata (`list_jvms`), plus on Linux any
+/// `java` process in `/proc` that does not publish perf data.
+pub fn list_daemons() -> Result<Vec<JvmDaemon>, String> {
+    let active = fs::read_to_string(get_state_file_path()).unwrap_or_default();
+    let active = active.trim().to_string();
+    let jdks = cached_jdks().unwrap_or_default();
+    let processes = user_processes();
+
+    let mut daemons = Vec::new();
+    for jvm in list_jvms()? {
+        // The full command line adds JVM options such as jdtls' -Declipse.product
+        let cmdline = processes
+            .iter()
+            .find(|process| process.pid == jvm.pid)
+            .map(|process| process.command.clone());
+        let command = cmdline.or_else(|| jvm.command.clone()).unwrap_or_default();
+        let Some(kind) = classify(&command).or_else(|| classify(jvm.command.as_deref().unwrap_or_default())) else {
+            continue;
+        };
+        let java_home = jvm.java_home.as_deref().map(jdk_home);
+        let process = processes.iter().find(|process| process.pid == jvm.pid);
+        let expected_home = daemon_expected_home(process, &active);
+        daemons.push(JvmDaemon {
+            pid: jvm.pid,
+            kind,
+            on_expected_jdk: on_expected_jdk(java_home.as_deref(), expected_home.as_deref()),
+            expected_home,
+            java_home,
+            version_major: jvm.version_major,
+            command,
+        });
+    }
+
+    for process in &processes {
+        if daemons.iter().any(|daemon| daemon.pid == process.pid) {
+            continue;
+        }
+        let Some(exe) = process.exe.as_deref().filter(|exe| exe.file_name().is_some_and(|name| name == "java")) else {
+            continue;
+        };
+        let Some(kind) = classify(&process.command) else {
+            continue;
+        };
+        let java_home = exe
+            .parent()
+            .and_then(Path::parent)
+            .map(|home| jdk_home(&home.to_string_lossy()));
+        let version_major = java_home
+            .as_deref()
+            .and_then(|home| jdks.iter().find(|jdk| same_jdk(&jdk.home, home)))
+            .map(|jdk| jdk.version_major);
+        let expected_home = daemon_expected_home(Some(process), &active);
+        daemons.push(JvmDaemon {
+            pid: process.pid,
+            kind,
+            on_expected_jdk: on_expected_jdk(java_home.as_deref(), expected_home.as_deref()),
+            expected_home,
+            java_home,
+            version_major,
+            command: process.command.clone(),
+        });
+    }
+    daemons.sort_by_key(|daemon| daemon.pid);
+    Ok(daemons)
+}
+
+/// Stop every daemon on a known JDK other than the one that applies to it
+/// (see `JvmDaemon::expected_home`): SIGTERM first,
+/// which runs the JVM's shutdown hooks so the daemon deregisters cleanly, then
+/// SIGKILL if it is still running after `STOP_TIMEOUT`. Daemons whose JDK is
+/// unknown are reported as `Skipped`.
+///
+/// `gradle --stop` and `mvnd --stop` are not used; they stop every daemon of
+/// that version, including the ones already on the right JDK.
+pub fn stop_stale_daemons() -> Result<Vec<DaemonStop>, String> {
+    let active = fs::read_to_string(get_state_file_path()).unwrap_or_default();
+    if active.trim().is_empty() {
+        return Err("No active JDK; select one before stopping daemons".to_string());
+    }
+    let (stale, other): (Vec<JvmDaemon>, Vec<JvmDaemon>) =
+        list_daemons()?.into_iter().partition(|daemon| daemon.is_stale());
+
+    for daemon in &stale {
+        let _ = signal(daemon.pid, "TERM");
+    }
+    let started = Instant::now();
+    while started.elapsed() < STOP_TIMEOUT && stale.iter().any(|daemon| is_running(daemon.pid)) {
+        std::thread::sleep(Duration::from_millis(200));
+    }
+
+    let mut stops: Vec<DaemonStop> = stale
+        .into_iter()
+        .map(|daemon| {
+            // The pid may belong to a new process by now
+            let outcome = if !is_running(daemon.pid) || !is_same_daemon(&daemon) {
+                StopOutcome::Terminated
+            } else if signal(daemon.pid, "KILL").is_ok() {
+                StopOutcome::Killed
+            } else {
+                StopOutcome::Failed
+            };
+            DaemonStop { daemon, outcome }
+        })
+        .collect();
+    stops.extend(
+        other
+            .into_iter()
+            .filter(|daemon| daemon.on_expected_jdk.is_none())
+            .map(|daemon| DaemonStop {
+                daemon,
+                outcome: StopOutcome::Skipped,
+            }),
+    );
+    Ok(stops)
+}
+
+/// JDK a daemon should run on, resolved like `processes::expected_home`: its
+/// session pin, then the project pin, else `active`. Daemons change directory
+/// (Gradle's to its registry), so the project is looked up from the `PWD`
+/// inherited from the shell that started the build.
+fn daemon_expected_home(process: Option<&ProcessEntry>, active: &str) -> Option<String> {
+    process
+        .and_then(|process| {
+            let dir = process.env("PWD").map(PathBuf::from).or_else(|| process.cwd.clone());
+            expected_home(process, ProcessKind::Jvm, dir.as_deref())
+        })
+        .or_else(|| Some(active.to_string()))
+        .filter(|home| !home.is_empty())
+}
+
+fn on_expected_jdk(java_home: Option<&str>, expected_home: Option<&str>) -> Option<bool> {
+    Some(same_jdk(java_home?, expected_home?))
+}
+
+/// Whether `daemon.pid` still runs a daemon of the same kind.
+fn is_same_daemon(daemon: &JvmDaemon) -> bool {
+    command_line(daemon.pid).is_some_and(|command| classify(&command) == Some(daemon.kind))
+}
+
+fn classify(command: &str) -> Option<DaemonKind> {
+    if command.contains("org.gradle.launcher.daemon.bootstrap.GradleDaemon") {
+        Some(DaemonKind::Gradle)
+    } else if command.contains("org.jetbrains.kotlin.daemon.KotlinCompileDaemon") {
+        Some(DaemonKind::Kotlin)
+    } else if command.contains("org.mvndaemon.mvnd.common.MavenDaemon")
+        || command.contains("org.mvndaemon.mvnd.daemon.Server")
+    {
+        Some(DaemonKind::Mvnd)
+    } else if command.contains("org.eclipse.equinox.launcher")
+        && (command.contains("jdt.ls") || command.contains("jdtls"))
+    {
+        Some(DaemonKind::Jdtls)
+    } else {
+        None
+    }
+}
+
+/// Java 8 reports `<jdk>/jre` as `java.home`; use the JDK above it.
+fn jdk_home(java_home: &str) -> String {
+    let path = Path::new(java_home);
+    match path.parent() {
+        Some(parent) if path.ends_with("jre") && parent.join("bin").join("java").is_file() => {
+            parent.to_string_lossy().to_string()
+        }
+        _ => java_home.to_string(),
+    }
+}
+
+fn signal(pid: u32, signal: &str) -> Result<(), String> {
+    let status = Command::new("kill")
+        .arg(format!("-{}", signal))
+        .arg(pid.to_string())
+        .status()
+        .map_err(|e| format!("Failed to run kill: {e}"))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("kill -{} {} failed", signal, pid))
+    }
+}
+
+fn is_running(pid: u32) -> bool {
+    if Path::new("/proc").is_dir() {
+        return Path::new(&format!("/proc/{}", pid)).exists();
+    }
+    Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{classify, daemon_expected_home, on_expected_jdk, DaemonKind, JvmDaemon};
+    use crate::processes::ProcessEntry;
+    use std::fs;
+
+    fn daemon(on_expected_jdk: Option<bool>) -> JvmDaemon {
+        JvmDaemon {
+            pid: 4242,
+            kind: DaemonKind::Gradle,
+            java_home: on_expected_jdk.map(|_| "/opt/jdk-17".to_string()),
+            version_major: None,
+            expected_home: Some("/opt/jdk-21".to_string()),
+            on_expected_jdk,
+            command: "java org.gradle.launcher.daemon.bootstrap.GradleDaemon 8.5".to_string(),
+        }
+    }
+
+    #[test]
+    fn only_daemons_on_a_known_other_jdk_are_stale() {
+        assert!(daemon(Some(false)).is_stale());
+        assert!(!daemon(Some(true)).is_stale());
+        assert!(!daemon(None).is_stale());
+    }
+
+    #[test]
+    fn daemon_started_in_a_pinned_project_expects_the_pinned_jdk() {
+        let root = std::env::temp_dir().join(format!("jdk-pulse-daemon-{}", std::process::id()));
+        let (pinned, active, project) = (root.join("jdk-17"), root.join("jdk-21"), root.join("project"));
+        for dir in [&pinned, &active, &project.join("src")] {
+            fs::create_dir_all(dir).unwrap();
+        }
+        fs::write(project.join(".java-version"), format!("{}\n", pinned.display())).unwrap();
+        let (pinned, active) = (pinned.to_string_lossy().to_string(), active.to_string_lossy().to_string());
+
+        // Gradle daemons run from ~/.gradle/daemon/<version>; PWD is where the build started
+        let cwd = project.join("src").to_string_lossy().to_string();
+        let process = ProcessEntry::with_env(4242, &[("PWD", cwd.as_str())]);
+        let expected = daemon_expected_home(Some(&process), &active);
+        assert_eq!(expected.as_deref(), Some(pinned.as_str()));
+        assert_eq!(on_expected_jdk(Some(&pinned), expected.as_deref()), Some(true));
+
+        let elsewhere = ProcessEntry::with_env(4243, &[("PWD", "/")]);
+        let expected = daemon_expected_home(Some(&elsewhere), &active);
+        assert_eq!(on_expected_jdk(Some(&pinned), expected.as_deref()), Some(false));
+        assert_eq!(on_expected_jdk(None, expected.as_deref()), None);
+        assert_eq!(daemon_expected_home(None, ""), None);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn classifies_by_main_class() {
+        assert_eq!(classify(&daemon(None).command), Some(DaemonKind::Gradle));
+        assert_eq!(
+            classify("java -Declipse.product=org.eclipse.jdt.ls.core.product -jar org.eclipse.equinox.launcher_1.6.jar"),
+            Some(DaemonKind::Jdtls)
+        );
+        assert_eq!(classify("java -jar app.jar"), None);
+    }
+}
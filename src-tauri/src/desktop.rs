@@ -0,0 +1,185 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::activation::{is_jdk_bin_dir, is_jdk_root, rewrite_path};
+use super::cached_jdks;
+use super::integration::xdg_config_home;
+
+/// Used when the user manager's PATH cannot be read.
+const FALLBACK_PATH: &str = "/usr/local/bin:/usr/bin:/bin";
+
+/// `$XDG_CONFIG_HOME/environment.d/50-jdk-pulse.conf`, read by the systemd
+/// user manager when the graphical session starts.
+pub fn get_environment_d_path() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or("Could not determine home directory")?;
+    Ok(xdg_config_home(&home)
+        .join("environment.d")
+        .join("50-jdk-pulse.conf"))
+}
+
+/// Publish `java_home` and the profile variables `env` to desktop-launched
+/// apps: `environment.d` for future sessions, and the running systemd user
+/// manager and D-Bus activation environment for apps started from now on.
+/// `unset` names variables of the previous profile to take back out.
+/// Enabled by `integrations.desktop`.
+pub fn sync_desktop_environment(
+    java_home: &str,
+    env: &BTreeMap<String, String>,
+    unset: &[String],
+) -> Result<(), String> {
+    let conf = get_environment_d_path()?;
+    if let Some(parent) = conf.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Error creating {}: {e}", parent.display()))?;
+    }
+    fs::write(&conf, environment_d_contents(java_home, env))
+        .map_err(|e| format!("Error writing {}: {e}", conf.display()))?;
+
+    let bin = Path::new(java_home).join("bin");
+    let (entries, previous_home) = manager_environment();
+    let mut homes = known_jdk_homes();
+    homes.extend(previous_home);
+    homes.push(java_home.to_string());
+    let path = rewrite_path(&entries, &bin.to_string_lossy(), &homes).join(":");
+    let mut assignments = vec![format!("JAVA_HOME={}", java_home), format!("PATH={}", path)];
+    assignments.extend(env.iter().map(|(key, value)| format!("{}={}", key, value)));
+    run("systemctl", &["--user", "set-environment"], &assignments)?;
+    if !unset.is_empty() {
+        run("systemctl", &["--user", "unset-environment"], unset)?;
+    }
+    // The activation environment cannot drop a variable, only empty it
+    assignments.extend(unset.iter().map(|key| format!("{}=", key)));
+    run("dbus-update-activation-environment", &[], &assignments)
+}
+
+/// Delete the `environment.d` file and take JAVA_HOME, the JDK `bin` and the
+/// profile variables it listed back out of the running user manager.
+pub fn remove_desktop_integration() -> Result<(), String> {
+    let conf = get_environment_d_path()?;
+    let mut unset = vec!["JAVA_HOME".to_string()];
+    if conf.exists() {
+        let contents = fs::read_to_string(&conf).unwrap_or_default();
+        unset.extend(profile_keys(&contents));
+        fs::remove_file(&conf).map_err(|e| format!("Error removing {}: {e}", conf.display()))?;
+    }
+
+    let (mut entries, previous_home) = manager_environment();
+    let mut homes = known_jdk_homes();
+    homes.extend(previous_home);
+    entries.retain(|entry| !homes.iter().any(|home| is_jdk_bin_dir(Path::new(entry), home)));
+    let path = format!("PATH={}", entries.join(":"));
+    run("systemctl", &["--user", "unset-environment"], &unset)?;
+    run("systemctl", &["--user", "set-environment"], std::slice::from_ref(&path))?;
+    let mut assignments: Vec<String> = unset.iter().map(|key| format!("{}=", key)).collect();
+    assignments.push(path);
+    run("dbus-update-activation-environment", &[], &assignments)
+}
+
+/// The `environment.d` file: JAVA_HOME, the JDK `bin` in front of PATH, and
+/// the profile variables.
+fn environment_d_contents(java_home: &str, env: &BTreeMap<String, String>) -> String {
+    let bin = Path::new(java_home).join("bin");
+    let mut contents = format!(
+        "# Written by jdk-pulse on every JDK switch; remove with `jdk-pulse desktop remove`\nJAVA_HOME={}\nPATH={}:${{PATH}}\n",
+        environment_d_escape(java_home),
+        environment_d_escape(&bin.to_string_lossy())
+    );
+    for (key, value) in env {
+        contents.push_str(&format!("{}={}\n", key, environment_d_escape(value)));
+    }
+    contents
+}
+
+/// Profile variables listed in an `environment.d` file written by `environment_d_contents`.
+fn profile_keys(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once('=').map(|(key, _)| key))
+        .filter(|key| *key != "JAVA_HOME" && *key != "PATH")
+        .map(|key| key.to_string())
+        .collect()
+}
+
+/// PATH entries and JAVA_HOME of the running systemd user manager
+/// (`systemctl --user show-environment`). JAVA_HOME is only returned when it
+/// is a JDK root.
+fn manager_environment() -> (Vec<String>, Option<String>) {
+    let output = Command::new("systemctl")
+        .args(["--user", "show-environment"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).to_string())
+        .unwrap_or_default();
+    // Values with special characters are shown as $'...'; ignore those rather than unquote
+    let value = |key: &str| {
+        output
+            .lines()
+            .find_map(|line| line.strip_prefix(key))
+            .filter(|value| !value.is_empty() && !value.starts_with("$'"))
+            .map(|value| value.to_string())
+    };
+    let path = value("PATH=").unwrap_or_else(|| FALLBACK_PATH.to_string());
+    let entries = path
+        .split(':')
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| entry.to_string())
+        .collect();
+    // A JAVA_HOME that is not a JDK root (often /usr) must not take /usr/bin
+    // out of every desktop app's PATH
+    (entries, value("JAVA_HOME=").filter(|home| is_jdk_root(home)))
+}
+
+fn known_jdk_homes() -> Vec<String> {
+    cached_jdks()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|jdk| jdk.home)
+        .collect()
+}
+
+fn run(program: &str, args: &[&str], assignments: &[String]) -> Result<(), String> {
+    let status = Command::new(program)
+        .args(args)
+        .args(assignments)
+        .status()
+        .map_err(|e| format!("Failed to run {}: {e}", program))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("{} {} failed ({})", program, args.join(" "), status))
+    }
+}
+
+/// environment.d expands `$VAR` and backslash escapes, so both are escaped.
+fn environment_d_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('$', "\\$")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{environment_d_contents, profile_keys};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn environment_d_lists_the_profile_variables() {
+        let env = BTreeMap::from([
+            ("JAVA_TOOL_OPTIONS".to_string(), "-Dfile.encoding=UTF-8".to_string()),
+            ("MAVEN_OPTS".to_string(), "-Xmx2g -Dhome=$HOME".to_string()),
+        ]);
+        let contents = environment_d_contents("/opt/jdk-8", &env);
+        assert_eq!(
+            contents,
+            "# Written by jdk-pulse on every JDK switch; remove with `jdk-pulse desktop remove`\n\
+             JAVA_HOME=/opt/jdk-8\n\
+             PATH=/opt/jdk-8/bin:${PATH}\n\
+             JAVA_TOOL_OPTIONS=-Dfile.encoding=UTF-8\n\
+             MAVEN_OPTS=-Xmx2g -Dhome=\\$HOME\n"
+        );
+        assert_eq!(profile_keys(&contents), vec!["JAVA_TOOL_OPTIONS", "MAVEN_OPTS"]);
+        assert!(profile_keys(&environment_d_contents("/opt/jdk-8", &BTreeMap::new())).is_empty());
+    }
+}
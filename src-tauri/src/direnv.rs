@@ -0,0 +1,82 @@
+use std::path::{Path, PathBuf};
+
+use super::config::get_config_file_path;
+use super::hooks::{sh_escape, sh_path};
+use super::integration::{
+    managed_block, remove_managed_block, upsert_managed_block, xdg_config_home, IntegrationChange,
+};
+use super::project::project_file_names;
+use super::{get_profile_env_file_path, get_state_file_path};
+
+const INTEGRATION_NAME: &str = "direnv";
+
+/// direnv library file providing `use jdkpulse`, e.g. `~/.config/direnv/lib/jdk-pulse.sh`.
+/// direnv sources every `*.sh` in that directory before `.envrc`.
+pub fn get_direnv_lib_path() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or("Could not determine home directory")?;
+    Ok(xdg_config_home(&home)
+        .join("direnv")
+        .join("lib")
+        .join("jdk-pulse.sh"))
+}
+
+/// Install (or refresh) the direnv library; `cli` is the jdk-pulse executable it calls.
+pub fn install_direnv_integration(cli: &Path) -> Result<IntegrationChange, String> {
+    let file = get_direnv_lib_path()?;
+    let (action, backup) = upsert_managed_block(&file, &managed_block(&generate_direnv_lib(cli)))?;
+    Ok(IntegrationChange {
+        integration: INTEGRATION_NAME.to_string(),
+        file: file.to_string_lossy().to_string(),
+        action,
+        backup,
+    })
+}
+
+/// Remove the direnv library; the file is deleted since jdk-pulse owns it.
+pub fn remove_direnv_integration() -> Result<IntegrationChange, String> {
+    let file = get_direnv_lib_path()?;
+    let (action, backup) = remove_managed_block(&file, true)?;
+    Ok(IntegrationChange {
+        integration: INTEGRATION_NAME.to_string(),
+        file: file.to_string_lossy().to_string(),
+        action,
+        backup,
+    })
+}
+
+/// `use_jdkpulse [spec]` for `.envrc`. The environment comes from `jdk-pulse env`.
+/// Without a spec it follows the effective JDK, so direnv re-evaluates when the
+/// session, the global selection, the profile or any project file up the tree
+/// changes; the config is watched either way for aliases and hidden JDKs.
+pub fn generate_direnv_lib(cli: &Path) -> String {
+    let cli = sh_escape(&cli.to_string_lossy());
+    let config_file = sh_path(&get_config_file_path());
+    let state_file = sh_path(&get_state_file_path());
+    let env_file = sh_path(&get_profile_env_file_path());
+    let project_files = project_file_names().collect::<Vec<_>>().join(" ");
+
+    format!(
+        r#"# Usage in .envrc: use jdkpulse [profile|id|home|version]
+use_jdkpulse() {{
+  watch_file "{config_file}"
+  if [ -z "$1" ]; then
+    watch_file "{state_file}" "{env_file}"
+    if [ -n "$JDK_PULSE_SESSION" ]; then
+      watch_file "$JDK_PULSE_SESSION"
+    fi
+    local dir="$PWD" file
+    while :; do
+      for file in {project_files}; do
+        watch_file "$dir/$file"
+      done
+      [ -z "$dir" ] && break
+      dir="${{dir%/*}}"
+    done
+  fi
+  local activation
+  activation="$("{cli}" env "$@")" || return 1
+  eval "$activation"
+  log_status "jdk-pulse: JAVA_HOME=$JAVA_HOME"
+}}"#
+    )
+}
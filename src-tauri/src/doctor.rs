@@ -0,0 +1,368 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use super::hooks::Shell;
+use super::processes::{find_stale_processes, StaleProcess};
+use super::shadowing::{describe_hit, scan_path, ToolOnPath, PATH_TOOLS};
+use super::shims::get_shims_dir_path;
+use super::{extract_quoted_segment, get_active_jdk, get_state_file_path, parse_major_version};
+
+/// How long the login shell may take to start and answer
+const SHELL_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Variables passed to the login shell; everything else is rebuilt by its
+/// profile, as in a fresh terminal.
+const LOGIN_ENV: &[&str] = &[
+    "HOME", "USER", "LOGNAME", "SHELL", "TERM", "LANG", "LC_ALL", "TMPDIR", "XDG_CONFIG_HOME",
+    "XDG_DATA_HOME", "XDG_STATE_HOME", "XDG_RUNTIME_DIR",
+];
+
+/// PATH a login starts from before the profile runs.
+const LOGIN_PATH: &str = "/usr/local/bin:/usr/bin:/bin:/usr/sbin:/sbin";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Warn,
+    Fail,
+}
+
+/// One line of the doctor report.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DoctorCheck {
+    pub name: String,
+    pub status: CheckStatus,
+    pub message: String,
+}
+
+/// Result of `run_doctor_sync`: what `~/.jdk_current` says against what a new
+/// login shell actually gets.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DoctorReport {
+    pub state_file_ok: bool,
+    pub state_file_java_home: Option<String>,
+    /// Login shell that was spawned
+    pub shell: String,
+    pub shell_java_home: Option<String>,
+    /// `java` found on the login shell's PATH
+    pub shell_java_path: Option<String>,
+    /// First line of `java -version`
+    pub shell_java_version: Option<String>,
+    /// `java`, `javac`, `mvn` and `gradle` on the login shell's PATH, in lookup order
+    pub path_tools: Vec<ToolOnPath>,
+    /// Shells and JVMs still on another JDK (Linux)
+    pub stale_processes: Vec<StaleProcess>,
+    pub checks: Vec<DoctorCheck>,
+    pub notes: Vec<String>,
+}
+
+impl DoctorReport {
+    /// Worst status of all checks.
+    pub fn status(&self) -> CheckStatus {
+        self.checks
+            .iter()
+            .map(|check| check.status)
+            .max()
+            .unwrap_or(CheckStatus::Ok)
+    }
+
+    fn check(&mut self, name: &str, status: CheckStatus, message: String) {
+        self.checks.push(DoctorCheck {
+            name: name.to_string(),
+            status,
+            message,
+        });
+    }
+}
+
+/// What the login shell reported.
+struct ShellProbe {
+    java_home: Option<String>,
+    path: Option<String>,
+    java_path: Option<String>,
+    java_version: Option<String>,
+}
+
+/// Spawn the user's login shell with its profile (`$SHELL -l -i`), capture
+/// `$JAVA_HOME`, its PATH and `java -version`, and compare them with
+/// `~/.jdk_current`. Every `java`, `javac`, `mvn` and `gradle` on that PATH is
+/// listed, and the first `java`/`javac` must belong to the selected JDK.
+pub fn run_doctor_sync() -> Result<DoctorReport, String> {
+    let state_file = get_state_file_path();
+    let state_home = fs::read_to_string(&state_file)
+        .ok()
+        .map(|home| home.trim().to_string())
+        .filter(|home| !home.is_empty());
+    let shell = std::env::var("SHELL")
+        .ok()
+        .filter(|shell| !shell.is_empty())
+        .unwrap_or_else(|| "/bin/sh".to_string());
+
+    let mut report = DoctorReport {
+        state_file_ok: false,
+        state_file_java_home: state_home.clone(),
+        shell: shell.clone(),
+        shell_java_home: None,
+        shell_java_path: None,
+        shell_java_version: None,
+        path_tools: Vec::new(),
+        stale_processes: Vec::new(),
+        checks: Vec::new(),
+        notes: Vec::new(),
+    };
+
+    match &state_home {
+        None => report.check(
+            "state_file",
+            CheckStatus::Fail,
+            format!("No JDK selected in {}; run `jdk-pulse --set <version>`", state_file.display()),
+        ),
+        Some(home) if !Path::new(home).is_dir() => {
+            report.check("state_file", CheckStatus::Fail, format!("{} does not exist", home))
+        }
+        Some(home) if !java_executable(home).is_file() => report.check(
+            "state_file",
+            CheckStatus::Fail,
+            format!("{} has no bin/java", home),
+        ),
+        Some(home) => {
+            report.state_file_ok = true;
+            report.check("state_file", CheckStatus::Ok, format!("{} selects {}", state_file.display(), home));
+        }
+    }
+
+    if Path::new("/proc").is_dir() {
+        report.stale_processes = find_stale_processes()?;
+        if report.stale_processes.is_empty() {
+            report.check(
+                "stale_processes",
+                CheckStatus::Ok,
+                "No shells or JVMs on another JDK".to_string(),
+            );
+        } else {
+            let listing = report
+                .stale_processes
+                .iter()
+                .map(|process| format!("{} {} ({})", process.pid, process.name, process.java_home))
+                .collect::<Vec<_>>()
+                .join(", ");
+            report.check(
+                "stale_processes",
+                CheckStatus::Warn,
+                format!("{} processes on an old JDK: {}", report.stale_processes.len(), listing),
+            );
+        }
+    }
+
+    let probe = match probe_login_shell(&shell, &mut report.notes) {
+        Ok(probe) => probe,
+        Err(e) => {
+            report.check("login_shell", CheckStatus::Fail, e);
+            return Ok(report);
+        }
+    };
+    report.shell_java_home = probe.java_home.clone();
+    report.shell_java_path = probe.java_path.clone();
+    report.shell_java_version = probe.java_version.clone();
+
+    match (&probe.java_home, &state_home) {
+        (None, _) => report.check(
+            "shell_java_home",
+            CheckStatus::Fail,
+            "JAVA_HOME is not set in a new login shell; run `jdk-pulse shell install`".to_string(),
+        ),
+        (Some(java_home), Some(home)) if same_path(java_home, home) => {
+            report.check("shell_java_home", CheckStatus::Ok, format!("JAVA_HOME={}", java_home))
+        }
+        (Some(java_home), Some(home)) => report.check(
+            "shell_java_home",
+            CheckStatus::Fail,
+            format!("A new login shell has JAVA_HOME={}, expected {}", java_home, home),
+        ),
+        (Some(java_home), None) => report.check(
+            "shell_java_home",
+            CheckStatus::Warn,
+            format!("JAVA_HOME={}, but no JDK is selected", java_home),
+        ),
+    }
+
+    let mut extra_homes: Vec<String> = state_home.iter().cloned().collect();
+    extra_homes.extend(probe.java_home.clone());
+    report.path_tools = scan_path(
+        probe.path.as_deref().unwrap_or(LOGIN_PATH),
+        PATH_TOOLS,
+        &extra_homes,
+        probe.java_home.as_deref(),
+    );
+    let shims_dir = get_shims_dir_path();
+    for entry in report.path_tools.clone() {
+        let name = format!("path_{}", entry.tool);
+        let listing = entry.hits.iter().map(describe_hit).collect::<Vec<_>>().join(", ");
+        let is_jdk_tool = matches!(entry.tool.as_str(), "java" | "javac");
+        match (entry.hits.first(), &state_home) {
+            (None, _) if entry.tool == "java" => {
+                report.check(&name, CheckStatus::Fail, "No `java` on the login shell's PATH".to_string())
+            }
+            (None, _) if is_jdk_tool => report.check(
+                &name,
+                CheckStatus::Warn,
+                format!("No `{}` on the login shell's PATH", entry.tool),
+            ),
+            (None, _) => {}
+            (Some(_), Some(home)) if is_jdk_tool => {
+                let first = &entry.hits[0];
+                let belongs = Path::new(&first.path).starts_with(&shims_dir)
+                    || first.jdk_home.as_deref().is_some_and(|jdk| same_path(jdk, home));
+                if belongs {
+                    report.check(&name, CheckStatus::Ok, listing);
+                } else {
+                    report.check(
+                        &name,
+                        CheckStatus::Fail,
+                        format!(
+                            "`{}` resolves to {}, not the selected JDK {}; PATH order: {}",
+                            entry.tool,
+                            first.jdk_home.as_deref().unwrap_or("something outside any JDK"),
+                            home,
+                            listing
+                        ),
+                    );
+                }
+            }
+            (Some(_), None) if is_jdk_tool => report.check(&name, CheckStatus::Warn, listing),
+            (Some(_), _) => report.check(&name, CheckStatus::Ok, listing),
+        }
+    }
+
+    let expected_major = get_active_jdk()
+        .ok()
+        .flatten()
+        .map(|jdk| jdk.version_major)
+        .filter(|major| *major > 0);
+    match &probe.java_version {
+        None => report.check(
+            "java_version",
+            CheckStatus::Fail,
+            "`java -version` failed in the login shell".to_string(),
+        ),
+        Some(version) => {
+            let major = extract_quoted_segment(version).map(|version| parse_major_version(&version));
+            match (major, expected_major) {
+                (Some(major), Some(expected)) if major != expected => report.check(
+                    "java_version",
+                    CheckStatus::Fail,
+                    format!("{} (expected Java {})", version, expected),
+                ),
+                _ => report.check("java_version", CheckStatus::Ok, version.clone()),
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Run the probe script in a login shell started from the home directory with
+/// a login-like environment, so the current process (or a session override)
+/// does not leak in.
+fn probe_login_shell(shell: &str, notes: &mut Vec<String>) -> Result<ShellProbe, String> {
+    let name = Path::new(shell)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let (program, args, script) = match (Shell::from_name(&name), name.as_str()) {
+        (Some(Shell::Zsh | Shell::Bash), _) => (shell.to_string(), vec!["-l", "-i", "-c"], POSIX_PROBE),
+        (Some(Shell::Fish), _) => (shell.to_string(), vec!["-l", "-i", "-c"], FISH_PROBE),
+        (None, "sh" | "dash" | "ksh" | "mksh") => (shell.to_string(), vec!["-l", "-c"], POSIX_PROBE),
+        _ => {
+            notes.push(format!(
+                "doctor cannot script {}; checked /bin/sh -l instead, which does not load its hook",
+                name
+            ));
+            ("/bin/sh".to_string(), vec!["-l", "-c"], POSIX_PROBE)
+        }
+    };
+
+    let mut command = Command::new(&program);
+    command.args(&args).arg(script).env_clear().env("PATH", LOGIN_PATH);
+    for key in LOGIN_ENV {
+        if let Some(value) = std::env::var_os(key) {
+            command.env(key, value);
+        }
+    }
+    command.env("SHELL", shell);
+    if let Some(home) = dirs::home_dir() {
+        command.current_dir(home);
+    }
+    let output = run_with_timeout(command, &program)?;
+
+    let value = |marker: &str| {
+        output
+            .lines()
+            .find_map(|line| line.strip_prefix(marker))
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    let java_version = output
+        .lines()
+        .skip_while(|line| *line != VERSION_MARKER)
+        .find(|line| line.contains(" version \""))
+        .map(|line| line.trim().to_string());
+    Ok(ShellProbe {
+        java_home: value("__jdk_pulse_home="),
+        path: value("__jdk_pulse_path="),
+        java_path: value("__jdk_pulse_java="),
+        java_version,
+    })
+}
+
+const VERSION_MARKER: &str = "__jdk_pulse_version";
+
+const POSIX_PROBE: &str = r#"printf '%s\n' "__jdk_pulse_home=$JAVA_HOME" "__jdk_pulse_java=$(command -v java)" "__jdk_pulse_path=$PATH" __jdk_pulse_version; java -version 2>&1"#;
+
+const FISH_PROBE: &str = r#"printf '%s\n' "__jdk_pulse_home=$JAVA_HOME" "__jdk_pulse_java="(command -s java) "__jdk_pulse_path="(string join : -- $PATH) __jdk_pulse_version; java -version 2>&1"#;
+
+/// Stdout of `command`, killed after `SHELL_TIMEOUT` (a profile waiting for input).
+fn run_with_timeout(mut command: Command, program: &str) -> Result<String, String> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {e}", program))?;
+    let started = Instant::now();
+    loop {
+        match child.try_wait() {
+            Ok(Some(_)) => break,
+            Ok(None) if started.elapsed() < SHELL_TIMEOUT => std::thread::sleep(Duration::from_millis(50)),
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!(
+                    "{} did not finish within {}s",
+                    program,
+                    SHELL_TIMEOUT.as_secs()
+                ));
+            }
+        }
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Failed to read {} output: {e}", program))?;
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn java_executable(home: &str) -> PathBuf {
+    Path::new(home).join("bin").join("java")
+}
+
+/// Equal after resolving symlinks (e.g. `/usr/lib/jvm/default` or `/private/var`).
+fn same_path(a: &str, b: &str) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => Path::new(a) == Path::new(b),
+    }
+}
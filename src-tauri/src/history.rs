@@ -0,0 +1,229 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{get_state_dir_path, switch_jdk};
+
+/// Maximum number of activations kept in the history file
+const MAX_HISTORY_ENTRIES: usize = 50;
+
+/// A single JDK activation, as recorded by `set_active_jdk`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// User that performed the switch
+    pub who: String,
+    /// Seconds since the Unix epoch
+    pub when: u64,
+    /// JAVA_HOME before the switch (None if nothing was selected)
+    pub from: Option<String>,
+    /// JAVA_HOME after the switch
+    pub to: String,
+}
+
+/// The activation history with an undo/redo cursor, stored in `history.json`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct History {
+    /// Activations, oldest first
+    pub entries: Vec<HistoryEntry>,
+    /// Number of entries in effect. `back` moves it down and `forward` up
+    /// again; a new switch drops the entries past it.
+    pub cursor: usize,
+}
+
+/// `history.json` as written before the cursor existed: a plain list.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredHistory {
+    Cursor(History),
+    Entries(Vec<HistoryEntry>),
+}
+
+impl History {
+    /// Record a switch. Switching to the current JDK records nothing, and
+    /// repeating the switch `forward` would make just moves the cursor, so
+    /// the undone entries after it survive.
+    fn record(&mut self, entry: HistoryEntry) {
+        if entry.from.as_deref() == Some(entry.to.as_str()) {
+            return;
+        }
+        if let Some(next) = self.entries.get(self.cursor) {
+            if next.from == entry.from && next.to == entry.to {
+                self.cursor += 1;
+                return;
+            }
+        }
+        self.entries.truncate(self.cursor);
+        self.entries.push(entry);
+        if self.entries.len() > MAX_HISTORY_ENTRIES {
+            let excess = self.entries.len() - MAX_HISTORY_ENTRIES;
+            self.entries.drain(..excess);
+        }
+        self.cursor = self.entries.len();
+    }
+
+    /// JAVA_HOME that `back` restores: the one before the last switch in effect.
+    pub fn previous(&self) -> Option<&str> {
+        let entry = self.entries.get(self.cursor.checked_sub(1)?)?;
+        entry.from.as_deref()
+    }
+
+    /// JAVA_HOME that `forward` restores: the target of the last undone switch.
+    pub fn next(&self) -> Option<&str> {
+        self.entries.get(self.cursor).map(|entry| entry.to.as_str())
+    }
+}
+
+/// Load the activation history. A missing file is an empty history.
+pub fn load_history() -> Result<History, String> {
+    let path = get_history_file_path();
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(_) => return Ok(History::default()),
+    };
+    if contents.trim().is_empty() {
+        return Ok(History::default());
+    }
+
+    let stored = serde_json::from_str(&contents)
+        .map_err(|e| format!("Failed to parse history file {}: {e}", path.display()))?;
+    let mut history = match stored {
+        StoredHistory::Cursor(history) => history,
+        StoredHistory::Entries(entries) => History {
+            cursor: entries.len(),
+            entries,
+        },
+    };
+    history.cursor = history.cursor.min(history.entries.len());
+    Ok(history)
+}
+
+fn save_history(history: &History) -> Result<(), String> {
+    let path = get_history_file_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Error creating state directory: {e}"))?;
+    }
+    let json = serde_json::to_string_pretty(history)
+        .map_err(|e| format!("Error serializing history: {e}"))?;
+    fs::write(&path, json).map_err(|e| format!("Error writing history file: {e}"))
+}
+
+/// Record a switch made by `set_active_jdk`, dropping the oldest entries beyond the limit.
+pub fn record_activation(from: Option<&str>, to: &str) -> Result<(), String> {
+    let mut history = load_history()?;
+
+    let when = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let who = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string());
+
+    history.record(HistoryEntry {
+        who,
+        when,
+        from: from.map(|s| s.to_string()),
+        to: to.to_string(),
+    });
+    save_history(&history)
+}
+
+/// Undo the last switch in effect, restoring the selection before it.
+/// Repeated calls walk further back; `go_forward` redoes.
+pub fn go_back() -> Result<String, String> {
+    let mut history = load_history()?;
+    let home = history
+        .previous()
+        .ok_or("No previous JDK in history")?
+        .to_string();
+    let home = switch_jdk(&home, false, None)?;
+    history.cursor -= 1;
+    save_history(&history)?;
+    Ok(home)
+}
+
+/// Redo the switch undone last by `go_back`.
+pub fn go_forward() -> Result<String, String> {
+    let mut history = load_history()?;
+    let home = history.next().ok_or("No undone JDK switch to redo")?.to_string();
+    let home = switch_jdk(&home, false, None)?;
+    history.cursor += 1;
+    save_history(&history)?;
+    Ok(home)
+}
+
+fn get_history_file_path() -> PathBuf {
+    get_state_dir_path().join("history.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{History, HistoryEntry, MAX_HISTORY_ENTRIES};
+
+    fn entry(from: Option<&str>, to: &str) -> HistoryEntry {
+        HistoryEntry {
+            who: "me".to_string(),
+            when: 0,
+            from: from.map(str::to_string),
+            to: to.to_string(),
+        }
+    }
+
+    fn switches(homes: &[&str]) -> History {
+        let mut history = History::default();
+        let mut from = None;
+        for home in homes {
+            history.record(entry(from, home));
+            from = Some(*home);
+        }
+        history
+    }
+
+    #[test]
+    fn keeps_a_bounded_history() {
+        let homes: Vec<String> = (0..MAX_HISTORY_ENTRIES + 10).map(|i| format!("/jdk/{}", i)).collect();
+        let refs: Vec<&str> = homes.iter().map(|h| h.as_str()).collect();
+        let history = switches(&refs);
+        assert_eq!(history.entries.len(), MAX_HISTORY_ENTRIES);
+        assert_eq!(history.cursor, MAX_HISTORY_ENTRIES);
+        assert_eq!(history.entries[0].from.as_deref(), Some("/jdk/9"));
+        assert_eq!(history.entries.last().unwrap().to, format!("/jdk/{}", MAX_HISTORY_ENTRIES + 9));
+    }
+
+    #[test]
+    fn skips_switches_that_change_nothing() {
+        let mut history = switches(&["/jdk/8", "/jdk/17"]);
+        history.record(entry(Some("/jdk/17"), "/jdk/17"));
+        assert_eq!(history.entries.len(), 2);
+        assert_eq!(history.cursor, 2);
+    }
+
+    #[test]
+    fn moves_back_and_forward() {
+        let mut history = switches(&["/jdk/8", "/jdk/17", "/jdk/21"]);
+        assert_eq!(history.previous(), Some("/jdk/17"));
+        assert_eq!(history.next(), None);
+
+        // back, back: 21 -> 17 -> 8
+        history.cursor -= 1;
+        assert_eq!(history.previous(), Some("/jdk/8"));
+        assert_eq!(history.next(), Some("/jdk/21"));
+        history.cursor -= 1;
+        assert_eq!(history.previous(), None);
+        assert_eq!(history.next(), Some("/jdk/17"));
+
+        // Switching to where forward leads is a redo and keeps the rest
+        history.record(entry(Some("/jdk/8"), "/jdk/17"));
+        assert_eq!(history.entries.len(), 3);
+        assert_eq!(history.next(), Some("/jdk/21"));
+
+        // Any other switch drops the undone entries
+        history.record(entry(Some("/jdk/17"), "/jdk/11"));
+        assert_eq!(history.entries.len(), 3);
+        assert_eq!(history.next(), None);
+        assert_eq!(history.previous(), Some("/jdk/17"));
+        assert_eq!(history.entries.last().unwrap().to, "/jdk/11");
+    }
+}
@@ -0,0 +1,770 @@
+use serde::{Deserialize, Serialize};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+use super::project::project_file_names;
+use super::session::get_sessions_dir_path;
+use super::shadowing::is_executable;
+use super::{get_jdk_homes_file_path, get_profile_env_file_path, get_state_file_path};
+
+/// Shells jdk-pulse can generate a prompt hook for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Shell {
+    Zsh,
+    Bash,
+    Fish,
+    Nushell,
+    Elvish,
+    Xonsh,
+    /// tcsh, and csh for startup-only activation
+    Tcsh,
+}
+
+impl Shell {
+    pub const ALL: &'static [Shell] = &[
+        Shell::Zsh,
+        Shell::Bash,
+        Shell::Fish,
+        Shell::Nushell,
+        Shell::Elvish,
+        Shell::Xonsh,
+        Shell::Tcsh,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Shell::Zsh => "zsh",
+            Shell::Bash => "bash",
+            Shell::Fish => "fish",
+            Shell::Nushell => "nushell",
+            Shell::Elvish => "elvish",
+            Shell::Xonsh => "xonsh",
+            Shell::Tcsh => "tcsh",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Shell> {
+        if name == "csh" {
+            return Some(Shell::Tcsh);
+        }
+        Shell::ALL.iter().copied().find(|shell| shell.name() == name)
+    }
+}
+
+/// Hook code for `shell`, without the managed-block markers.
+///
+/// Every hook follows the same contract: on startup, take over the session of
+/// the parent shell or start a new one (see `session::SESSION_PID_ENV_VAR`);
+/// before each prompt, read the session state file (`JDK_PULSE_SESSION`) or
+/// else `~/.jdk_current`, export `JAVA_HOME`, put `$JAVA_HOME/bin` first on
+/// `PATH` after dropping the `bin` and `jre/bin` directories of the known JDK
+/// homes (`jdk-homes`),
+/// and export the variables of the active profile (`~/.jdk_current.env`).
+///
+/// The bash, zsh and fish hooks also switch on directory change: a JDK pinned
+/// by a project file (see `project`) applies between the session override and
+/// `~/.jdk_current`. They resolve it by running the jdk-pulse CLI at `cli`,
+/// which the tcsh hook also runs for `env --format csh`.
+pub fn generate_hook(shell: Shell, cli: &Path) -> String {
+    match shell {
+        Shell::Zsh => format!(
+            "{}\n_jdk_pulse_on_chpwd() {{\n  _jdk_pulse_chpwd\n  _jdk_pulse_apply\n}}\nautoload -Uz add-zsh-hook\nadd-zsh-hook chpwd _jdk_pulse_on_chpwd\n_jdk_pulse_precmd() {{\n  _jdk_pulse_project_changed && _jdk_pulse_resolve_project\n  _jdk_pulse_apply\n}}\nadd-zsh-hook precmd _jdk_pulse_precmd\n_jdk_pulse_on_chpwd\n",
+            posix_hook_functions(cli)
+        ),
+        Shell::Bash => format!(
+            "{}\n_jdk_pulse_prompt() {{\n  if [ \"$PWD\" != \"$_JDK_PULSE_PWD\" ]; then\n    _jdk_pulse_chpwd\n  elif _jdk_pulse_project_changed; then\n    _jdk_pulse_resolve_project\n  fi\n  _jdk_pulse_apply\n}}\nif [[ \"$PROMPT_COMMAND\" != *\"_jdk_pulse_prompt\"* ]]; then\n  PROMPT_COMMAND=\"_jdk_pulse_prompt${{PROMPT_COMMAND:+; $PROMPT_COMMAND}}\"\nfi\n_jdk_pulse_prompt\n",
+            posix_hook_functions(cli)
+        ),
+        Shell::Fish => fish_hook(cli),
+        Shell::Nushell => nushell_hook(),
+        Shell::Elvish => elvish_hook(),
+        Shell::Xonsh => xonsh_hook(),
+        Shell::Tcsh => tcsh_hook(cli),
+    }
+}
+
+/// Functions shared by the bash and zsh hooks.
+///
+/// `_jdk_pulse_chpwd` finds the nearest project file with shell tests alone
+/// and asks the CLI (`home --project`) only when that file differs from the
+/// last one, so moving around inside a project costs no process spawns.
+/// Before each prompt, `_jdk_pulse_project_changed` compares the project file
+/// and `~/.jdk_current` against a per-shell stamp file (`test -nt`, as the
+/// shells have no builtin for mtimes), so in-place edits are picked up too.
+fn posix_hook_functions(cli: &Path) -> String {
+    let state_file = sh_path(&get_state_file_path());
+    let homes_file = sh_path(&get_jdk_homes_file_path());
+    let env_file = sh_path(&get_profile_env_file_path());
+    let sessions_dir = sh_path(&get_sessions_dir_path());
+    let cli = sh_escape(&cli.to_string_lossy());
+    let project_files = project_file_names().collect::<Vec<_>>().join(" ");
+
+    format!(
+        r#"{session_init}
+_JDK_PULSE_STAMP="{sessions_dir}/$$.stamp"
+[ -d "{sessions_dir}" ] || mkdir -p "{sessions_dir}" 2>/dev/null
+_jdk_pulse_chpwd() {{
+  local _dir="$PWD" _file _found=""
+  while :; do
+    for _file in {project_files}; do
+      if [ -f "$_dir/$_file" ]; then
+        _found="$_dir/$_file"
+        break 2
+      fi
+    done
+    [ -z "$_dir" ] && break
+    _dir="${{_dir%/*}}"
+  done
+  _JDK_PULSE_PWD="$PWD"
+  [ "$_found" = "$_JDK_PULSE_PROJECT_FILE" ] && ! _jdk_pulse_project_changed && return
+  _JDK_PULSE_PROJECT_FILE="$_found"
+  _jdk_pulse_resolve_project
+}}
+_jdk_pulse_project_changed() {{
+  [ -n "$_JDK_PULSE_PROJECT_FILE" ] || return 1
+  [ "$_JDK_PULSE_PROJECT_FILE" -nt "$_JDK_PULSE_STAMP" ] || [ "{state_file}" -nt "$_JDK_PULSE_STAMP" ]
+}}
+_jdk_pulse_resolve_project() {{
+  _JDK_PULSE_PROJECT_HOME=""
+  if [ -n "$_JDK_PULSE_PROJECT_FILE" ]; then
+    : > "$_JDK_PULSE_STAMP" 2>/dev/null
+    _JDK_PULSE_PROJECT_HOME="$("{cli}" home --project "$PWD")"
+  fi
+}}
+_jdk_pulse_apply() {{
+  local new_home=""
+  if [ -s "$JDK_PULSE_SESSION" ]; then
+    new_home="$(cat "$JDK_PULSE_SESSION" 2>/dev/null)"
+  elif [ -n "$_JDK_PULSE_PROJECT_HOME" ]; then
+    new_home="$_JDK_PULSE_PROJECT_HOME"
+  elif [ -f "{state_file}" ]; then
+    new_home="$(cat "{state_file}" 2>/dev/null)"
+  fi
+  if [ -n "$new_home" ] && [ -d "$new_home" ] && [ "$JAVA_HOME" != "$new_home" ]; then
+    local _homes=("$new_home") _home _line _rest="$PATH:" _entry _kept="" _skip
+    [ -f "$JAVA_HOME/release" ] && _homes+=("$JAVA_HOME")
+    if [ -f "{homes_file}" ]; then
+      while IFS= read -r _line; do
+        [ -n "$_line" ] && _homes+=("$_line")
+      done < "{homes_file}"
+    fi
+    while [ -n "$_rest" ]; do
+      _entry="${{_rest%%:*}}"
+      _rest="${{_rest#*:}}"
+      _skip=""
+      for _home in "${{_homes[@]}}"; do
+        _home="${{_home%/}}"
+        case "${{_entry%/}}" in
+          "$_home/bin"|"$_home/jre/bin") _skip=1; break ;;
+        esac
+      done
+      [ -n "$_skip" ] || _kept="$_kept${{_kept:+:}}$_entry"
+    done
+    export JAVA_HOME="$new_home"
+    export PATH="$new_home/bin${{_kept:+:$_kept}}"
+  fi
+  _jdk_pulse_apply_profile
+}}
+_jdk_pulse_apply_profile() {{
+  local _env="" _line _name
+  if [ -f "{env_file}" ]; then
+    _env="$(cat "{env_file}" 2>/dev/null)"
+  fi
+  [ "$_env" = "$_JDK_PULSE_PROFILE_ENV" ] && return
+  for _name in "${{_JDK_PULSE_PROFILE_VARS[@]}}"; do
+    unset "$_name"
+  done
+  _JDK_PULSE_PROFILE_VARS=()
+  _JDK_PULSE_PROFILE_ENV="$_env"
+  while IFS= read -r _line; do
+    case "$_line" in
+      ''|'#'*) continue ;;
+    esac
+    _name="${{_line%%=*}}"
+    export "$_name=${{_line#*=}}"
+    _JDK_PULSE_PROFILE_VARS+=("$_name")
+  done <<< "$_env"
+}}"#,
+        session_init = posix_session_init(),
+    )
+}
+
+/// Start a session for this shell unless it inherited one from its parent
+/// shell. A new session file is removed first, as a dead shell with the same
+/// pid may have left one behind.
+fn posix_session_init() -> String {
+    let sessions_dir = sh_path(&get_sessions_dir_path());
+    format!(
+        r#"if [ -z "$JDK_PULSE_SESSION" ] || {{ [ "$JDK_PULSE_SESSION_PID" != "$$" ] && [ "$JDK_PULSE_SESSION_PID" != "$PPID" ]; }}; then
+  export JDK_PULSE_SESSION="{sessions_dir}/$$"
+  rm -f "$JDK_PULSE_SESSION"
+fi
+export JDK_PULSE_SESSION_PID="$$""#
+    )
+}
+
+/// fish hook, run on every `fish_prompt` event and on `PWD` changes. PATH is
+/// changed as a global (not universal) variable so other fish sessions are
+/// unaffected, and the JDK `bin` is moved to the front instead of being added twice.
+/// The project cache key holds the mtimes of the project file and
+/// `~/.jdk_current` (`path mtime`, fish 3.5+), so in-place edits are picked up.
+fn fish_hook(cli: &Path) -> String {
+    let state_file = fish_path(&get_state_file_path());
+    let homes_file = fish_path(&get_jdk_homes_file_path());
+    let env_file = fish_path(&get_profile_env_file_path());
+    let sessions_dir = fish_path(&get_sessions_dir_path());
+    let cli = fish_path(cli);
+    let project_files = project_file_names().collect::<Vec<_>>().join(" ");
+
+    format!(
+        r#"if not set -q JDK_PULSE_SESSION; or not contains -- "$JDK_PULSE_SESSION_PID" $fish_pid (ps -o ppid= -p $fish_pid 2>/dev/null | string trim)
+    set -gx JDK_PULSE_SESSION "{sessions_dir}/$fish_pid"
+    rm -f "$JDK_PULSE_SESSION"
+end
+set -gx JDK_PULSE_SESSION_PID $fish_pid
+function _jdk_pulse_chpwd --on-variable PWD
+    set -l dir $PWD
+    set -l found ""
+    while true
+        for file in {project_files}
+            if test -f "$dir/$file"
+                set found "$dir/$file"
+                break
+            end
+        end
+        test -n "$found"; and break
+        test -z "$dir"; and break
+        set dir (string replace -r '/[^/]*$' '' -- "$dir")
+    end
+    set -g _jdk_pulse_project_file "$found"
+    _jdk_pulse_refresh_project
+    _jdk_pulse_apply
+end
+function _jdk_pulse_refresh_project
+    set -l key "$_jdk_pulse_project_file" (path mtime -- "$_jdk_pulse_project_file" "{state_file}" 2>/dev/null)
+    if set -q _jdk_pulse_project_key; and test "$key" = "$_jdk_pulse_project_key"
+        return
+    end
+    set -g _jdk_pulse_project_key "$key"
+    set -g _jdk_pulse_project_home ""
+    if test -n "$_jdk_pulse_project_file"
+        set -g _jdk_pulse_project_home (command "{cli}" home --project "$PWD")
+    end
+end
+function _jdk_pulse_prompt --on-event fish_prompt
+    _jdk_pulse_refresh_project
+    _jdk_pulse_apply
+end
+function _jdk_pulse_apply
+    set -l new_home ""
+    if test -s "$JDK_PULSE_SESSION"
+        set new_home (cat "$JDK_PULSE_SESSION" 2>/dev/null)
+    else if test -n "$_jdk_pulse_project_home"
+        set new_home "$_jdk_pulse_project_home"
+    else if test -f "{state_file}"
+        set new_home (cat "{state_file}" 2>/dev/null)
+    end
+    if test -n "$new_home"; and test -d "$new_home"; and test "$JAVA_HOME" != "$new_home"
+        set -l homes "$new_home"
+        test -f "$JAVA_HOME/release"; and set -a homes "$JAVA_HOME"
+        test -f "{homes_file}"; and set -a homes (string match -v -- '' < "{homes_file}")
+        set -l bins
+        for home in (string trim -r -c / -- $homes)
+            set -a bins "$home/bin" "$home/jre/bin"
+        end
+        set -l new_path
+        for entry in $PATH
+            contains -- (string trim -r -c / -- "$entry") $bins; or set -a new_path $entry
+        end
+        set -gx JAVA_HOME "$new_home"
+        set -gx PATH "$new_home/bin" $new_path
+    end
+    _jdk_pulse_apply_profile
+end
+function _jdk_pulse_apply_profile
+    set -l env_lines
+    if test -f "{env_file}"
+        set env_lines (cat "{env_file}" 2>/dev/null)
+    end
+    set -l env_key (string join \n -- $env_lines)
+    if set -q _jdk_pulse_profile_env; and test "$_jdk_pulse_profile_env" = "$env_key"
+        return
+    end
+    for name in $_jdk_pulse_profile_vars
+        set -e $name
+    end
+    set -g _jdk_pulse_profile_vars
+    set -g _jdk_pulse_profile_env "$env_key"
+    for line in $env_lines
+        string match -qr '^(#|$)' -- $line; and continue
+        set -l kv (string split -m 1 = -- $line)
+        set -gx $kv[1] $kv[2]
+        set -a _jdk_pulse_profile_vars $kv[1]
+    end
+end
+_jdk_pulse_chpwd
+"#
+    )
+}
+
+/// nushell hook for `config.nu`, appended to `$env.config.hooks.pre_prompt`.
+fn nushell_hook() -> String {
+    let state_file = nu_path(&get_state_file_path());
+    let homes_file = nu_path(&get_jdk_homes_file_path());
+    let env_file = nu_path(&get_profile_env_file_path());
+    let sessions_dir = nu_path(&get_sessions_dir_path());
+
+    format!(
+        r##"let _jdk_pulse_owners = [($nu.pid | into string) (^ps -o ppid= -p $nu.pid | complete | get stdout | str trim)]
+if ($env.JDK_PULSE_SESSION? == null) or (($env.JDK_PULSE_SESSION_PID? | default "") not-in $_jdk_pulse_owners) {{
+    $env.JDK_PULSE_SESSION = ({sessions_dir} | path join ($nu.pid | into string))
+    rm --force $env.JDK_PULSE_SESSION
+}}
+$env.JDK_PULSE_SESSION_PID = ($nu.pid | into string)
+def --env _jdk_pulse_apply [] {{
+    mut state_file = {state_file}
+    if ($env.JDK_PULSE_SESSION | path exists) and ((open --raw $env.JDK_PULSE_SESSION | str trim) != "") {{
+        $state_file = $env.JDK_PULSE_SESSION
+    }}
+    if ($state_file | path exists) {{
+        let new_home = (open --raw $state_file | str trim)
+        if ($new_home != "") and ($new_home | path exists) and ($env.JAVA_HOME? != $new_home) {{
+            let homes_file = {homes_file}
+            let known = if ($homes_file | path exists) {{ open --raw $homes_file | lines }} else {{ [] }}
+            let previous = ($env.JAVA_HOME? | default "")
+            let previous = if ($previous != "") and ($previous | path join "release" | path exists) {{ [$previous] }} else {{ [] }}
+            let homes = ([$new_home] | append $previous | append $known | where {{|h| $h != "" }})
+            $env.JAVA_HOME = $new_home
+            let bin = ($new_home | path join "bin")
+            let path = if ($env.PATH | describe) == "string" {{ $env.PATH | split row (char esep) }} else {{ $env.PATH }}
+            let bins = ($homes | each {{|h| [($h | path join "bin") ($h | path join "jre" "bin")] }} | flatten)
+            $env.PATH = ($path | where {{|p| ($p | str trim --right --char "/") not-in $bins }} | prepend $bin)
+        }}
+    }}
+    _jdk_pulse_apply_profile
+}}
+def --env _jdk_pulse_apply_profile [] {{
+    let env_file = {env_file}
+    let text = if ($env_file | path exists) {{ open --raw $env_file }} else {{ "" }}
+    if ($env._JDK_PULSE_PROFILE_ENV? == $text) {{ return }}
+    hide-env --ignore-errors ...($env._JDK_PULSE_PROFILE_VARS? | default [])
+    let pairs = ($text | lines | where {{|l| $l != "" and not ($l | str starts-with "#") }} | each {{|l| $l | split row --number 2 "=" }})
+    load-env ($pairs | reduce --fold {{}} {{|kv, acc| $acc | insert ($kv | first) ($kv | last) }})
+    $env._JDK_PULSE_PROFILE_VARS = ($pairs | each {{|kv| $kv | first }})
+    $env._JDK_PULSE_PROFILE_ENV = $text
+}}
+$env.config.hooks.pre_prompt = ($env.config.hooks.pre_prompt? | default [] | append {{|| _jdk_pulse_apply }})
+"##
+    )
+}
+
+/// elvish hook for `rc.elv`, appended to `edit:before-readline`.
+fn elvish_hook() -> String {
+    let state_file = elvish_path(&get_state_file_path());
+    let homes_file = elvish_path(&get_jdk_homes_file_path());
+    let env_file = elvish_path(&get_profile_env_file_path());
+    let sessions_dir = elvish_path(&get_sessions_dir_path());
+
+    format!(
+        r#"use path
+use str
+var _jdk_pulse_pid = (to-string $pid)
+if (or (not (has-env JDK_PULSE_SESSION)) (and (!=s $E:JDK_PULSE_SESSION_PID $_jdk_pulse_pid) (!=s $E:JDK_PULSE_SESSION_PID (try {{ str:trim-space (e:ps -o ppid= -p $_jdk_pulse_pid | slurp) }} catch e {{ put '' }})))) {{
+  set-env JDK_PULSE_SESSION {sessions_dir}/$_jdk_pulse_pid
+  try {{ e:rm -f $E:JDK_PULSE_SESSION }} catch e {{ }}
+}}
+set-env JDK_PULSE_SESSION_PID $_jdk_pulse_pid
+fn _jdk_pulse_read {{|file|
+  try {{ str:trim-space (slurp < $file) }} catch e {{ put '' }}
+}}
+var _jdk_pulse_profile_env = $nil
+var _jdk_pulse_profile_vars = []
+fn _jdk_pulse_apply_profile {{
+  var text = (try {{ slurp < {env_file} }} catch e {{ put '' }})
+  if (and (not-eq $_jdk_pulse_profile_env $nil) (==s $text $_jdk_pulse_profile_env)) {{
+    return
+  }}
+  for name $_jdk_pulse_profile_vars {{ unset-env $name }}
+  set _jdk_pulse_profile_vars = []
+  set _jdk_pulse_profile_env = $text
+  for line [(str:split "
+" $text)] {{
+    if (or (==s $line '') (str:has-prefix $line '#')) {{ continue }}
+    var kv = [(str:split &max=2 '=' $line)]
+    set-env $kv[0] $kv[1]
+    set _jdk_pulse_profile_vars = [$@_jdk_pulse_profile_vars $kv[0]]
+  }}
+}}
+fn _jdk_pulse_apply {{
+  var state-file = {state_file}
+  if (!=s (_jdk_pulse_read $E:JDK_PULSE_SESSION) '') {{
+    set state-file = $E:JDK_PULSE_SESSION
+  }}
+  var new-home = (_jdk_pulse_read $state-file)
+  if (and (!=s $new-home '') (path:is-dir $new-home) (!=s $E:JAVA_HOME $new-home)) {{
+    var homes = [$new-home (each {{|h| if (!=s $h '') {{ put $h }} }} [(str:split "\n" (_jdk_pulse_read {homes_file}))])]
+    if (and (!=s $E:JAVA_HOME '') (path:is-regular $E:JAVA_HOME'/release')) {{
+      set homes = [$@homes $E:JAVA_HOME]
+    }}
+    set-env JAVA_HOME $new-home
+    var bin = $new-home'/bin'
+    set paths = [$bin (each {{|p|
+      var keep = $true
+      var q = (str:trim-right $p '/')
+      for h $homes {{
+        set h = (str:trim-right $h '/')
+        if (or (==s $q $h'/bin') (==s $q $h'/jre/bin')) {{ set keep = $false }}
+      }}
+      if $keep {{ put $p }}
+    }} $paths)]
+  }}
+  _jdk_pulse_apply_profile
+}}
+set edit:before-readline = [$@edit:before-readline $_jdk_pulse_apply~]
+_jdk_pulse_apply
+"#
+    )
+}
+
+/// xonsh hook for `~/.xonshrc`, registered on the `on_pre_prompt` event.
+fn xonsh_hook() -> String {
+    let state_file = python_path(&get_state_file_path());
+    let homes_file = python_path(&get_jdk_homes_file_path());
+    let env_file = python_path(&get_profile_env_file_path());
+    let sessions_dir = python_path(&get_sessions_dir_path());
+
+    format!(
+        r#"import os as _jdk_pulse_os
+_jdk_pulse_pid = str(_jdk_pulse_os.getpid())
+if not ${{...}}.get('JDK_PULSE_SESSION') or ${{...}}.get('JDK_PULSE_SESSION_PID') not in (_jdk_pulse_pid, str(_jdk_pulse_os.getppid())):
+    $JDK_PULSE_SESSION = _jdk_pulse_os.path.join({sessions_dir}, _jdk_pulse_pid)
+    try:
+        _jdk_pulse_os.remove($JDK_PULSE_SESSION)
+    except OSError:
+        pass
+$JDK_PULSE_SESSION_PID = _jdk_pulse_pid
+_jdk_pulse_profile = {{'env': None, 'vars': []}}
+
+def _jdk_pulse_read(path):
+    try:
+        with open(path) as f:
+            return f.read()
+    except OSError:
+        return ''
+
+def _jdk_pulse_apply_profile():
+    text = _jdk_pulse_read({env_file})
+    if text == _jdk_pulse_profile['env']:
+        return
+    for name in _jdk_pulse_profile['vars']:
+        ${{...}}.pop(name, None)
+    names = []
+    for line in text.splitlines():
+        if not line or line.startswith('#') or '=' not in line:
+            continue
+        name, value = line.split('=', 1)
+        ${{...}}[name] = value
+        names.append(name)
+    _jdk_pulse_profile.update(env=text, vars=names)
+
+def _jdk_pulse_apply():
+    state_file = {state_file}
+    session = ${{...}}.get('JDK_PULSE_SESSION', '')
+    if session and _jdk_pulse_read(session).strip():
+        state_file = session
+    new_home = _jdk_pulse_read(state_file).strip()
+    if new_home and _jdk_pulse_os.path.isdir(new_home) and ${{...}}.get('JAVA_HOME') != new_home:
+        previous = ${{...}}.get('JAVA_HOME', '')
+        homes = [new_home] + _jdk_pulse_read({homes_file}).splitlines()
+        if previous and _jdk_pulse_os.path.isfile(_jdk_pulse_os.path.join(previous, 'release')):
+            homes.append(previous)
+        homes = [home.rstrip('/') for home in homes if home]
+        bins = [h + suffix for h in homes for suffix in ('/bin', '/jre/bin')]
+        for entry in [p for p in $PATH if p.rstrip('/') in bins]:
+            $PATH.remove(entry)
+        $JAVA_HOME = new_home
+        $PATH.insert(0, _jdk_pulse_os.path.join(new_home, 'bin'))
+    _jdk_pulse_apply_profile()
+
+@events.on_pre_prompt
+def _jdk_pulse_on_pre_prompt(**kwargs):
+    _jdk_pulse_apply()
+
+_jdk_pulse_apply()
+"#
+    )
+}
+
+/// Names the jdk-pulse CLI for hooks installed from the tray.
+pub const CLI_ENV_VAR: &str = "JDK_PULSE_CLI";
+
+/// The jdk-pulse CLI for hooks written by a process that is not the CLI
+/// itself (the tray): `$JDK_PULSE_CLI`, else the first `jdk-pulse` on `PATH`
+/// that is not the running executable. Hooks calling the tray binary would
+/// start another tray on every prompt, so there is no fallback.
+pub fn find_cli() -> Result<PathBuf, String> {
+    if let Some(cli) = std::env::var_os(CLI_ENV_VAR).filter(|cli| !cli.is_empty()) {
+        let cli = PathBuf::from(cli);
+        if !is_executable(&cli) {
+            return Err(format!("{} is set to {}, which is not an executable", CLI_ENV_VAR, cli.display()));
+        }
+        return Ok(cli);
+    }
+    let own = std::env::current_exe().ok();
+    find_on_path("jdk-pulse", &std::env::var_os("PATH").unwrap_or_default(), own.as_deref()).ok_or_else(|| {
+        format!(
+            "Could not find the jdk-pulse CLI on PATH; install it or set {} to its path",
+            CLI_ENV_VAR
+        )
+    })
+}
+
+/// First executable `name` in the `PATH`-style list `path`, skipping the file
+/// `exclude` (compared after resolving symlinks).
+fn find_on_path(name: &str, path: &OsStr, exclude: Option<&Path>) -> Option<PathBuf> {
+    let exclude = exclude.and_then(|exclude| exclude.canonicalize().ok());
+    std::env::split_paths(path)
+        .map(|dir| dir.join(name))
+        .filter(|candidate| is_executable(candidate))
+        .find(|candidate| exclude.is_none() || candidate.canonicalize().ok() != exclude)
+}
+
+/// tcsh/csh hook for `~/.tcshrc` or `~/.cshrc`. csh has no functions, so the
+/// `precmd` alias pipes `tcsh_apply_script` (kept in `_JDK_PULSE_SH`) to
+/// `/bin/sh` and evaluates its output. Plain csh has no `precmd`; there the
+/// environment is applied once per shell.
+fn tcsh_hook(cli: &Path) -> String {
+    let sessions_dir = sh_path(&get_sessions_dir_path());
+    let script = tcsh_apply_script(cli).replace('\'', "'\\''");
+
+    format!(
+        r#"set _jdk_pulse_owner = ""
+if ( $?JDK_PULSE_SESSION_PID ) set _jdk_pulse_owner = "$JDK_PULSE_SESSION_PID"
+set _jdk_pulse_ppid = `ps -o ppid= -p $$`
+if ( ! $?JDK_PULSE_SESSION || ( "$_jdk_pulse_owner" != "$$" && "$_jdk_pulse_owner" != "$_jdk_pulse_ppid" ) ) then
+  setenv JDK_PULSE_SESSION "{sessions_dir}/$$"
+  rm -f "$JDK_PULSE_SESSION"
+endif
+setenv JDK_PULSE_SESSION_PID $$
+unset _jdk_pulse_owner _jdk_pulse_ppid
+setenv _JDK_PULSE_SH '{script}'
+alias _jdk_pulse_apply 'eval "`printenv _JDK_PULSE_SH | /bin/sh`"'
+unsetenv _JDK_PULSE_KEY
+_jdk_pulse_apply
+if ( $?tcsh ) then
+  set _jdk_pulse_precmd = "`alias precmd`"
+  if ( "$_jdk_pulse_precmd" == "" ) then
+    alias precmd _jdk_pulse_apply
+  else if ( "$_jdk_pulse_precmd" !~ *_jdk_pulse_apply* ) then
+    alias precmd "$_jdk_pulse_precmd; _jdk_pulse_apply"
+  endif
+  unset _jdk_pulse_precmd
+endif
+"#
+    )
+}
+
+/// One-line sh script behind the tcsh `precmd`: it prints `jdk-pulse env
+/// --format csh` only when a checksum of the working directory,
+/// `~/.jdk_current`, the session file and the profile environment differs
+/// from `_JDK_PULSE_KEY`, and then records the new checksum there, whether or
+/// not the CLI succeeded. Prompts with nothing changed cost a `cksum`, not a
+/// CLI run, and an error such as a missing JDK is not retried until one of
+/// those inputs changes. The csh output unsets variables of the previous
+/// profile (see `activation::CSH_PROFILE_VARS_ENV_VAR`).
+fn tcsh_apply_script(cli: &Path) -> String {
+    let state_file = sh_path(&get_state_file_path());
+    let env_file = sh_path(&get_profile_env_file_path());
+    let cli = sh_escape(&cli.to_string_lossy());
+
+    format!(
+        r#"k=$( {{ pwd; cat "{state_file}" "$JDK_PULSE_SESSION" "{env_file}"; }} 2>/dev/null | cksum ); k=${{k%% *}}; if [ "$k" != "$_JDK_PULSE_KEY" ]; then "{cli}" env --format csh 2>/dev/null; echo "setenv _JDK_PULSE_KEY $k;"; fi"#
+    )
+}
+
+/// Render `path` for use inside a double-quoted shell string, relative to
+/// `$HOME` when possible so the hook survives dotfile syncing between machines.
+pub(crate) fn sh_path(path: &Path) -> String {
+    home_relative_path(path, sh_escape)
+}
+
+/// Like `sh_path`, for double-quoted fish strings.
+fn fish_path(path: &Path) -> String {
+    home_relative_path(path, |value| {
+        value.replace('\\', "\\\\").replace('"', "\\\"").replace('$', "\\$")
+    })
+}
+
+/// nushell expression for `path`: `($env.HOME | path join r#'...'#)` or a raw string.
+fn nu_path(path: &Path) -> String {
+    match home_relative(path) {
+        Some(relative) => format!("($env.HOME | path join r#'{}'#)", relative),
+        None => format!("r#'{}'#", path.to_string_lossy()),
+    }
+}
+
+/// elvish expression for `path`: `$E:HOME'/...'` with single quotes doubled.
+fn elvish_path(path: &Path) -> String {
+    let quote = |value: &str| format!("'{}'", value.replace('\'', "''"));
+    match home_relative(path) {
+        Some(relative) => format!("$E:HOME{}", quote(&format!("/{}", relative))),
+        None => quote(&path.to_string_lossy()),
+    }
+}
+
+/// Python expression for `path`, relative to the user's home when possible.
+fn python_path(path: &Path) -> String {
+    let quote = |value: &str| format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"));
+    match home_relative(path) {
+        Some(relative) => format!(
+            "_jdk_pulse_os.path.join(_jdk_pulse_os.path.expanduser('~'), {})",
+            quote(&relative)
+        ),
+        None => quote(&path.to_string_lossy()),
+    }
+}
+
+fn home_relative(path: &Path) -> Option<String> {
+    let home = dirs::home_dir()?;
+    path.strip_prefix(home)
+        .ok()
+        .map(|relative| relative.to_string_lossy().to_string())
+}
+
+fn home_relative_path(path: &Path, escape: impl Fn(&str) -> String) -> String {
+    match home_relative(path) {
+        Some(relative) => format!("$HOME/{}", escape(&relative)),
+        None => escape(&path.to_string_lossy()),
+    }
+}
+
+/// Escape a value for the inside of a double-quoted POSIX shell string.
+pub(crate) fn sh_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '"' | '\\' | '$' | '`') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_on_path, generate_hook, posix_session_init, Shell};
+    use std::io::Write;
+    use std::path::Path;
+    use std::process::{Command, Stdio};
+
+    const CLI: &str = "/opt/jdk pulse/bin/jdk-pulse";
+
+    #[cfg(unix)]
+    #[test]
+    fn finds_the_cli_on_path_but_not_the_running_binary() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = std::env::temp_dir().join(format!("jdk-pulse-cli-{}", std::process::id()));
+        let (tray, cli) = (root.join("tray"), root.join("cli"));
+        for dir in [&tray, &cli] {
+            std::fs::create_dir_all(dir).unwrap();
+            std::fs::write(dir.join("jdk-pulse"), "#!/bin/sh\n").unwrap();
+            std::fs::set_permissions(dir.join("jdk-pulse"), std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        let path = std::env::join_paths([&root.join("missing"), &tray, &cli]).unwrap();
+
+        assert_eq!(find_on_path("jdk-pulse", &path, None), Some(tray.join("jdk-pulse")));
+        assert_eq!(
+            find_on_path("jdk-pulse", &path, Some(&tray.join("jdk-pulse"))),
+            Some(cli.join("jdk-pulse"))
+        );
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn project_hooks_call_the_given_cli() {
+        let cli = Path::new(CLI);
+        for shell in [Shell::Bash, Shell::Zsh] {
+            assert!(generate_hook(shell, cli).contains(&format!("\"{}\" home --project", CLI)));
+        }
+        assert!(generate_hook(Shell::Fish, cli).contains(&format!("command \"{}\" home --project", CLI)));
+        assert!(generate_hook(Shell::Tcsh, cli).contains(&format!("\"{}\" env --format csh", CLI)));
+    }
+
+    /// Every command substitution in the fish hook fits on one line; a raw
+    /// newline inside `(...)` is a syntax error in fish.
+    #[test]
+    fn fish_hook_has_no_multiline_substitutions() {
+        for line in generate_hook(Shell::Fish, Path::new(CLI)).lines() {
+            assert_eq!(line.matches('(').count(), line.matches(')').count(), "{}", line);
+        }
+    }
+
+    #[test]
+    fn fish_hook_parses() {
+        let mut fish = match Command::new("fish")
+            .arg("--no-execute")
+            .stdin(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+        {
+            Ok(fish) => fish,
+            // fish is not installed
+            Err(_) => return,
+        };
+        fish.stdin
+            .take()
+            .unwrap()
+            .write_all(generate_hook(Shell::Fish, Path::new(CLI)).as_bytes())
+            .unwrap();
+        let output = fish.wait_with_output().unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    }
+
+    /// Run the POSIX session setup (`repeat` times) in a bash started by this
+    /// test, and return the resulting `JDK_PULSE_SESSION` and the shell's pid.
+    fn posix_session(env: &[(&str, &str)], repeat: usize) -> (String, String) {
+        let script = format!(
+            "{}\necho \"$JDK_PULSE_SESSION\"\necho \"$$\"\n",
+            vec![posix_session_init(); repeat].join("\nexport JDK_PULSE_SESSION=/tmp/pinned\n")
+        );
+        let output = Command::new("bash")
+            .arg("-c")
+            .arg(script)
+            .env_remove("JDK_PULSE_SESSION")
+            .env_remove("JDK_PULSE_SESSION_PID")
+            .envs(env.iter().copied())
+            .output()
+            .expect("bash runs");
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let mut lines = stdout.lines().map(str::to_string);
+        (lines.next().unwrap_or_default(), lines.next().unwrap_or_default())
+    }
+
+    #[test]
+    fn new_shell_starts_its_own_session() {
+        let (session, pid) = posix_session(&[], 1);
+        assert!(session.ends_with(&format!("/sessions/{}", pid)), "{}", session);
+    }
+
+    #[test]
+    fn nested_shell_inherits_the_parent_session() {
+        let parent = std::process::id().to_string();
+        let env = [("JDK_PULSE_SESSION", "/tmp/parent"), ("JDK_PULSE_SESSION_PID", parent.as_str())];
+        assert_eq!(posix_session(&env, 1).0, "/tmp/parent");
+    }
+
+    #[test]
+    fn shell_started_by_another_process_ignores_the_inherited_session() {
+        // e.g. a tmux pane whose server was started from a shell with pid 1
+        let env = [("JDK_PULSE_SESSION", "/tmp/launcher"), ("JDK_PULSE_SESSION_PID", "1")];
+        let (session, pid) = posix_session(&env, 1);
+        assert!(session.ends_with(&format!("/sessions/{}", pid)), "{}", session);
+    }
+
+    #[test]
+    fn sourcing_the_hook_again_keeps_the_session() {
+        assert_eq!(posix_session(&[], 2).0, "/tmp/pinned");
+    }
+}
@@ -0,0 +1,313 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::parse_major_version;
+use super::processes::current_user;
+
+/// First bytes of every perf-data file (always big-endian).
+const MAGIC: [u8; 4] = [0xca, 0xfe, 0xc0, 0xc0];
+/// Size of the version 2 prologue
+const PROLOGUE_SIZE: usize = 32;
+/// `data_type` of 64-bit counters
+const TYPE_LONG: u8 = b'J';
+/// `data_type` of byte vectors (strings)
+const TYPE_BYTE: u8 = b'B';
+
+/// One counter of a perf-data file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PerfValue {
+    Long(i64),
+    String(String),
+}
+
+/// A running JVM, read from its HotSpot perf-data file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JvmProcess {
+    pub pid: u32,
+    /// `java.home` of the JVM
+    pub java_home: Option<String>,
+    /// `java.version`, e.g. `17.0.9`
+    pub java_version: Option<String>,
+    pub version_major: Option<u32>,
+    /// `java.vm.version`, e.g. `17.0.9+9`
+    pub vm_version: Option<String>,
+    /// `sun.rt.javaCommand`: main class or `-jar` file, then arguments
+    pub command: Option<String>,
+    pub uptime_secs: Option<u64>,
+}
+
+impl JvmProcess {
+    /// Main class (or jar) from `command`.
+    pub fn main_class(&self) -> Option<&str> {
+        self.command.as_deref()?.split_whitespace().next()
+    }
+}
+
+/// Directories HotSpot writes perf data to: `hsperfdata_<user>` in the
+/// temporary directory (`/tmp` on Linux, `$TMPDIR` on macOS).
+pub fn get_hsperfdata_dirs() -> Vec<PathBuf> {
+    let Some(user) = current_user() else {
+        return Vec::new();
+    };
+    let name = format!("hsperfdata_{}", user);
+    let mut dirs = vec![std::env::temp_dir().join(&name)];
+    let tmp = Path::new("/tmp").join(&name);
+    if !dirs.contains(&tmp) {
+        dirs.push(tmp);
+    }
+    dirs
+}
+
+/// JVMs of the current user, like `jps`, without spawning or attaching to
+/// anything. JVMs started with `-XX:-UsePerfData` are not listed; files left
+/// behind by JVMs that are gone are skipped where `/proc` can tell.
+pub fn list_jvms() -> Result<Vec<JvmProcess>, String> {
+    let check_alive = Path::new("/proc").is_dir();
+    let mut jvms = Vec::new();
+    for dir in get_hsperfdata_dirs() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Some(pid) = entry.file_name().to_str().and_then(|name| name.parse::<u32>().ok()) else {
+                continue;
+            };
+            if check_alive && !Path::new(&format!("/proc/{}", pid)).exists() {
+                continue;
+            }
+            if jvms.iter().any(|jvm: &JvmProcess| jvm.pid == pid) {
+                continue;
+            }
+            // A JVM that is starting or exiting may leave a partial file
+            let counters = match fs::read(entry.path()) {
+                Ok(bytes) => parse_perf_data(&bytes),
+                Err(e) => Err(e.to_string()),
+            };
+            let Ok(counters) = counters else {
+                continue;
+            };
+            jvms.push(jvm_from_counters(pid, &counters));
+        }
+    }
+    jvms.sort_by_key(|jvm| jvm.pid);
+    Ok(jvms)
+}
+
+fn jvm_from_counters(pid: u32, counters: &BTreeMap<String, PerfValue>) -> JvmProcess {
+    let string = |name: &str| match counters.get(name) {
+        Some(PerfValue::String(value)) if !value.is_empty() => Some(value.clone()),
+        _ => None,
+    };
+    let long = |name: &str| match counters.get(name) {
+        Some(PerfValue::Long(value)) => Some(*value),
+        _ => None,
+    };
+
+    let now_millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_millis() as i64)
+        .unwrap_or(0);
+    // Wall-clock milliseconds at JVM creation; `sun.os.hrt.ticks` counts from
+    // an OS-defined origin and says nothing about the JVM's age
+    let uptime_secs = long("sun.rt.createVmBeginTime")
+        .filter(|begin| *begin > 0 && now_millis >= *begin)
+        .map(|begin| ((now_millis - begin) / 1000) as u64);
+    let java_version = string("java.property.java.version");
+    JvmProcess {
+        pid,
+        java_home: string("java.property.java.home"),
+        version_major: java_version
+            .as_deref()
+            .or(string("java.property.java.vm.specification.version").as_deref())
+            .map(parse_major_version)
+            .filter(|major| *major > 0),
+        java_version,
+        vm_version: string("java.property.java.vm.version"),
+        command: string("sun.rt.javaCommand"),
+        uptime_secs,
+    }
+}
+
+/// Parse a HotSpot perf-data file (format version 2) into its counters.
+///
+/// Layout: a 32-byte prologue (magic, byte order, version, entry offset and
+/// count), then entries of `entry_length`, `name_offset`, `vector_length`,
+/// `data_type`, `flags`, `data_units`, `data_variability`, `data_offset`,
+/// each with a NUL-terminated name and its data.
+pub fn parse_perf_data(bytes: &[u8]) -> Result<BTreeMap<String, PerfValue>, String> {
+    if bytes.len() < PROLOGUE_SIZE || bytes[0..4] != MAGIC {
+        return Err("Not a perf-data file".to_string());
+    }
+    let little_endian = bytes[4] == 1;
+    let major = bytes[5];
+    if major != 2 {
+        return Err(format!("Unsupported perf-data version {}.{}", major, bytes[6]));
+    }
+    let u32_at = |offset: usize| -> Result<usize, String> {
+        let raw: [u8; 4] = bytes
+            .get(offset..offset + 4)
+            .and_then(|slice| slice.try_into().ok())
+            .ok_or("Truncated perf-data file")?;
+        Ok(if little_endian { u32::from_le_bytes(raw) } else { u32::from_be_bytes(raw) } as usize)
+    };
+    let i64_at = |offset: usize| -> Result<i64, String> {
+        let raw: [u8; 8] = bytes
+            .get(offset..offset + 8)
+            .and_then(|slice| slice.try_into().ok())
+            .ok_or("Truncated perf-data file")?;
+        Ok(if little_endian { i64::from_le_bytes(raw) } else { i64::from_be_bytes(raw) })
+    };
+
+    let mut counters = BTreeMap::new();
+    let mut offset = u32_at(24)?;
+    let entries = u32_at(28)?;
+    for _ in 0..entries {
+        let entry_length = u32_at(offset)?;
+        if entry_length == 0 {
+            break;
+        }
+        let name_start = offset + u32_at(offset + 4)?;
+        let vector_length = u32_at(offset + 8)?;
+        let data_type = *bytes.get(offset + 12).ok_or("Truncated perf-data file")?;
+        let data_start = offset + u32_at(offset + 16)?;
+
+        let name = bytes
+            .get(name_start..)
+            .and_then(|rest| rest.split(|byte| *byte == 0).next())
+            .map(|name| String::from_utf8_lossy(name).to_string())
+            .ok_or("Truncated perf-data file")?;
+        let value = match (data_type, vector_length) {
+            (TYPE_LONG, 0) => Some(PerfValue::Long(i64_at(data_start)?)),
+            (TYPE_BYTE, length) if length > 0 => {
+                let data = bytes
+                    .get(data_start..data_start + length)
+                    .ok_or("Truncated perf-data file")?;
+                let text = data.split(|byte| *byte == 0).next().unwrap_or_default();
+                Some(PerfValue::String(String::from_utf8_lossy(text).to_string()))
+            }
+            _ => None,
+        };
+        if let Some(value) = value {
+            counters.insert(name, value);
+        }
+        offset += entry_length;
+    }
+    Ok(counters)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{jvm_from_counters, parse_perf_data, PerfValue, MAGIC, PROLOGUE_SIZE, TYPE_BYTE, TYPE_LONG};
+
+    /// Size of an entry header, before the name
+    const HEADER_SIZE: usize = 20;
+
+    enum Data<'a> {
+        Long(i64),
+        Bytes(&'a [u8]),
+    }
+
+    /// A version 2 perf-data file with the given counters, in either byte order.
+    fn perf_data(little_endian: bool, counters: &[(&str, Data)]) -> Vec<u8> {
+        let u32_bytes = |value: usize| {
+            let value = value as u32;
+            if little_endian { value.to_le_bytes() } else { value.to_be_bytes() }
+        };
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend([little_endian as u8, 2, 0, 1]);
+        bytes.resize(24, 0);
+        bytes.extend(u32_bytes(PROLOGUE_SIZE));
+        bytes.extend(u32_bytes(counters.len()));
+
+        for (name, data) in counters {
+            let name_offset = HEADER_SIZE;
+            let data_offset = (name_offset + name.len() + 1).next_multiple_of(8);
+            let (data_type, vector_length, payload) = match data {
+                Data::Long(value) => {
+                    let raw = if little_endian { value.to_le_bytes() } else { value.to_be_bytes() };
+                    (TYPE_LONG, 0, raw.to_vec())
+                }
+                Data::Bytes(raw) => (TYPE_BYTE, raw.len(), raw.to_vec()),
+            };
+            let entry_length = (data_offset + payload.len()).next_multiple_of(8);
+
+            let start = bytes.len();
+            bytes.extend(u32_bytes(entry_length));
+            bytes.extend(u32_bytes(name_offset));
+            bytes.extend(u32_bytes(vector_length));
+            bytes.extend([data_type, 0, 0, 0]);
+            bytes.extend(u32_bytes(data_offset));
+            bytes.extend(name.as_bytes());
+            bytes.resize(start + data_offset, 0);
+            bytes.extend(payload);
+            bytes.resize(start + entry_length, 0);
+        }
+        bytes
+    }
+
+    fn sample(little_endian: bool) -> Vec<u8> {
+        perf_data(
+            little_endian,
+            &[
+                ("sun.rt.createVmBeginTime", Data::Long(1_700_000_000_123)),
+                ("java.property.java.home", Data::Bytes(b"/opt/jdk-21\0\0\0\0\0")),
+                ("sun.rt.javaCommand", Data::Bytes(b"com.example.Main --port 8080\0")),
+            ],
+        )
+    }
+
+    #[test]
+    fn parses_both_byte_orders() {
+        for little_endian in [false, true] {
+            let counters = parse_perf_data(&sample(little_endian)).unwrap();
+            assert_eq!(counters.len(), 3);
+            assert_eq!(counters["sun.rt.createVmBeginTime"], PerfValue::Long(1_700_000_000_123));
+            assert_eq!(counters["java.property.java.home"], PerfValue::String("/opt/jdk-21".to_string()));
+            assert_eq!(
+                counters["sun.rt.javaCommand"],
+                PerfValue::String("com.example.Main --port 8080".to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        let bytes = sample(false);
+        assert!(parse_perf_data(&bytes[..PROLOGUE_SIZE - 1]).is_err());
+        assert!(parse_perf_data(&bytes[..bytes.len() - 4]).is_err());
+        assert!(parse_perf_data(&bytes[..PROLOGUE_SIZE + HEADER_SIZE / 2]).is_err());
+    }
+
+    #[test]
+    fn stops_at_an_entry_of_length_zero() {
+        let mut bytes = perf_data(true, &[("sun.os.hrt.frequency", Data::Long(1_000_000_000))]);
+        // Claim a second entry whose header is all zeros, as in a file still being written
+        bytes[28..32].copy_from_slice(&2u32.to_le_bytes());
+        bytes.extend([0; HEADER_SIZE]);
+        let counters = parse_perf_data(&bytes).unwrap();
+        assert_eq!(counters.len(), 1);
+        assert_eq!(counters["sun.os.hrt.frequency"], PerfValue::Long(1_000_000_000));
+    }
+
+    #[test]
+    fn uptime_comes_only_from_vm_creation_time() {
+        let bytes = perf_data(
+            false,
+            &[
+                ("sun.os.hrt.ticks", Data::Long(5_000_000_000)),
+                ("sun.os.hrt.frequency", Data::Long(1_000_000_000)),
+            ],
+        );
+        let jvm = jvm_from_counters(42, &parse_perf_data(&bytes).unwrap());
+        assert_eq!(jvm.uptime_secs, None);
+
+        let jvm = jvm_from_counters(42, &parse_perf_data(&sample(true)).unwrap());
+        assert!(jvm.uptime_secs.is_some());
+        assert_eq!(jvm.java_home.as_deref(), Some("/opt/jdk-21"));
+        assert_eq!(jvm.main_class(), Some("com.example.Main"));
+    }
+}
@@ -0,0 +1,210 @@
+use std::fs;
+use std::path::PathBuf;
+
+/// IDE integrations that `integrations.ides` may enable
+pub const KNOWN_IDE_INTEGRATIONS: &[&str] = &["vscode"];
+
+/// VS Code setting naming the JDK that runs the Java language server
+const VSCODE_JAVA_HOME_KEY: &str = "java.jdt.ls.java.home";
+
+/// Point each enabled IDE at `java_home`. Returns the settings files written;
+/// IDEs that are not installed are skipped.
+pub fn sync_ide_settings(ides: &[String], java_home: &str) -> Result<Vec<PathBuf>, String> {
+    let mut written = Vec::new();
+    for ide in ides {
+        if ide == "vscode" {
+            written.extend(sync_vscode(java_home)?);
+        }
+    }
+    Ok(written)
+}
+
+/// `<config dir>/Code/User/settings.json` (`~/.config` on Linux,
+/// `~/Library/Application Support` on macOS).
+pub fn get_vscode_settings_path() -> Result<PathBuf, String> {
+    let config = dirs::config_dir().ok_or("Could not determine config directory")?;
+    Ok(config.join("Code").join("User").join("settings.json"))
+}
+
+/// Set `java.jdt.ls.java.home` in the VS Code user settings. Nothing is
+/// created when the `User` directory is missing (VS Code not installed).
+fn sync_vscode(java_home: &str) -> Result<Option<PathBuf>, String> {
+    let settings = get_vscode_settings_path()?;
+    if !settings.parent().is_some_and(|dir| dir.is_dir()) {
+        return Ok(None);
+    }
+    let contents = match fs::read_to_string(&settings) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("Error reading {}: {e}", settings.display())),
+    };
+    let updated = set_json_string(&contents, VSCODE_JAVA_HOME_KEY, java_home)
+        .ok_or_else(|| format!("Could not update {}: not a JSON object", settings.display()))?;
+    if updated != contents {
+        fs::write(&settings, updated)
+            .map_err(|e| format!("Error writing {}: {e}", settings.display()))?;
+    }
+    Ok(Some(settings))
+}
+
+/// Set the top-level `key` of a JSON object to the string `value`. The text
+/// is edited in place so comments, key order and formatting survive (VS Code
+/// settings are JSON with comments). An absent key is added as the first
+/// member. None if `text` is not an object or `key` holds an object or array.
+pub(crate) fn set_json_string(text: &str, key: &str, value: &str) -> Option<String> {
+    let quoted = serde_json::to_string(value).ok()?;
+    let entry = format!("{}: {}", serde_json::to_string(key).ok()?, quoted);
+    if text.trim().is_empty() {
+        return Some(format!("{{\n    {}\n}}\n", entry));
+    }
+
+    let bytes = text.as_bytes();
+    let mut depth = 0usize;
+    let mut open = None;
+    let mut at_key = false;
+    let mut current_key: Option<String> = None;
+    let mut i = 0;
+    while i < bytes.len() {
+        if let Some(end) = comment_end(text, i) {
+            i = end;
+            continue;
+        }
+        match bytes[i] {
+            b'"' => {
+                let end = string_end(bytes, i)?;
+                if depth == 1 && at_key {
+                    current_key = serde_json::from_str(&text[i..end]).ok();
+                    at_key = false;
+                }
+                i = end;
+                continue;
+            }
+            b'{' | b'[' => {
+                if depth == 0 {
+                    if bytes[i] != b'{' || open.is_some() {
+                        return None;
+                    }
+                    open = Some(i + 1);
+                    at_key = true;
+                }
+                depth += 1;
+            }
+            b'}' | b']' => depth = depth.checked_sub(1)?,
+            b',' if depth == 1 => at_key = true,
+            b':' if depth == 1 && current_key.as_deref() == Some(key) => {
+                let start = skip_blank(text, i + 1);
+                let end = match bytes.get(start)? {
+                    b'"' => string_end(bytes, start)?,
+                    b'{' | b'[' => return None,
+                    _ => {
+                        let mut end = start;
+                        while end < bytes.len()
+                            && !matches!(bytes[end], b',' | b'}' | b'\n')
+                            && comment_end(text, end).is_none()
+                        {
+                            end += 1;
+                        }
+                        start + text[start..end].trim_end().len()
+                    }
+                };
+                return Some(format!("{}{}{}", &text[..start], quoted, &text[end..]));
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    let open = open?;
+    let first = skip_blank(text, open);
+    if bytes.get(first) == Some(&b'}') {
+        Some(format!("{}\n    {}\n{}", &text[..open], entry, &text[first..]))
+    } else {
+        Some(format!("{}\n    {},{}", &text[..open], entry, &text[open..]))
+    }
+}
+
+/// Index just past a `//` or `/* */` comment starting at `i`.
+fn comment_end(text: &str, i: usize) -> Option<usize> {
+    let rest = &text[i..];
+    if rest.starts_with("//") {
+        Some(rest.find('\n').map_or(text.len(), |n| i + n))
+    } else {
+        rest.strip_prefix("/*")
+            .map(|body| body.find("*/").map_or(text.len(), |n| i + 2 + n + 2))
+    }
+}
+
+/// Index just past the string literal starting at `i`.
+fn string_end(bytes: &[u8], i: usize) -> Option<usize> {
+    let mut j = i + 1;
+    while j < bytes.len() {
+        match bytes[j] {
+            b'\\' => j += 2,
+            b'"' => return Some(j + 1),
+            _ => j += 1,
+        }
+    }
+    None
+}
+
+/// First index at or after `i` that is not whitespace or a comment.
+fn skip_blank(text: &str, mut i: usize) -> usize {
+    let bytes = text.as_bytes();
+    while i < bytes.len() {
+        if let Some(end) = comment_end(text, i) {
+            i = end;
+        } else if bytes[i].is_ascii_whitespace() {
+            i += 1;
+        } else {
+            break;
+        }
+    }
+    i
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "java.jdt.ls.java.home";
+
+    #[test]
+    fn sets_the_java_home_in_vscode_settings() {
+        let cases: &[(&str, Option<&str>)] = &[
+            ("", Some("{\n    \"java.jdt.ls.java.home\": \"/opt/jdk-21\"\n}\n")),
+            ("{}", Some("{\n    \"java.jdt.ls.java.home\": \"/opt/jdk-21\"\n}")),
+            (
+                "{\n    // editor\n    \"editor.tabSize\": 4\n}\n",
+                Some("{\n    \"java.jdt.ls.java.home\": \"/opt/jdk-21\",\n    // editor\n    \"editor.tabSize\": 4\n}\n"),
+            ),
+            (
+                "{\n    \"java.jdt.ls.java.home\": \"/opt/jdk-17\", // pinned\n    \"a\": 1\n}",
+                Some("{\n    \"java.jdt.ls.java.home\": \"/opt/jdk-21\", // pinned\n    \"a\": 1\n}"),
+            ),
+            (
+                "{ \"java.jdt.ls.java.home\": null }",
+                Some("{ \"java.jdt.ls.java.home\": \"/opt/jdk-21\" }"),
+            ),
+            // Only the top-level key counts, not one nested in another setting
+            (
+                "{\n    \"[java]\": { \"java.jdt.ls.java.home\": \"/x\" }\n}",
+                Some("{\n    \"java.jdt.ls.java.home\": \"/opt/jdk-21\",\n    \"[java]\": { \"java.jdt.ls.java.home\": \"/x\" }\n}"),
+            ),
+            // A key mentioned inside a string value is not the key
+            (
+                "{ \"note\": \"java.jdt.ls.java.home\", \"java.jdt.ls.java.home\": \"/a\" }",
+                Some("{ \"note\": \"java.jdt.ls.java.home\", \"java.jdt.ls.java.home\": \"/opt/jdk-21\" }"),
+            ),
+            ("[1, 2]", None),
+            ("{ \"java.jdt.ls.java.home\": [] }", None),
+            ("{ \"unterminated: 1 }", None),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(
+                set_json_string(input, KEY, "/opt/jdk-21").as_deref(),
+                *expected,
+                "input: {input:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,453 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::config::load_config_or_default;
+use super::direnv::{generate_direnv_lib, get_direnv_lib_path};
+use super::hooks::{generate_hook, Shell};
+
+/// Markers delimiting the block jdk-pulse owns inside a user's rc file.
+pub const BLOCK_START: &str = "# >>> JDK-Pulse shell hook >>>";
+pub const BLOCK_END: &str = "# <<< JDK-Pulse shell hook <<<";
+
+/// First line inside a managed block: `# jdk-pulse <version>, checksum <hex>`.
+/// The checksum covers the hook body, so hand edits can be told apart from
+/// blocks that are merely outdated.
+const BLOCK_META_PREFIX: &str = "# jdk-pulse ";
+const BLOCK_META_CHECKSUM: &str = ", checksum ";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IntegrationAction {
+    Installed,
+    Updated,
+    Unchanged,
+    Removed,
+    /// A hand-edited block was left alone (use `upgrade --force` to replace it),
+    /// or a start marker without an end marker was found
+    Conflict,
+}
+
+/// What happened to one file during install or removal.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntegrationChange {
+    /// Shell name, or the non-shell integration (e.g. `direnv`)
+    pub integration: String,
+    pub file: String,
+    pub action: IntegrationAction,
+    /// Copy of the file taken before it was modified
+    pub backup: Option<String>,
+}
+
+/// Install the hooks for the shells enabled in the config (`integrations.shells`)
+/// that are in use: the login shell (`$SHELL`) and shells whose rc file (or,
+/// for fish, config directory) already exists. No rc file is created for a
+/// shell the user does not run. The hooks call the CLI at `cli`.
+pub fn install_shell_integration(cli: &Path) -> Result<Vec<IntegrationChange>, String> {
+    let config = load_config_or_default();
+    let mut shells = Vec::new();
+    for shell in config.integrations.shells.iter().filter_map(|name| Shell::from_name(name)) {
+        if is_login_shell(shell) || target_files(shell)?.iter().any(|file| is_in_use(shell, file)) {
+            shells.push(shell);
+        }
+    }
+    install_shells(&shells, cli)
+}
+
+/// Remove the managed blocks of every supported shell.
+pub fn remove_shell_integration() -> Result<Vec<IntegrationChange>, String> {
+    remove_shells(Shell::ALL)
+}
+
+/// Write (or refresh) the managed hook block in each shell's rc files, with
+/// hooks calling the CLI at `cli`.
+pub fn install_shells(shells: &[Shell], cli: &Path) -> Result<Vec<IntegrationChange>, String> {
+    let mut changes = Vec::new();
+    for &shell in shells {
+        let block = managed_block(&generate_hook(shell, cli));
+        for file in target_files(shell)? {
+            let (action, backup) = upsert_managed_block(&file, &block)?;
+            changes.push(IntegrationChange {
+                integration: shell.name().to_string(),
+                file: file.to_string_lossy().to_string(),
+                action,
+                backup,
+            });
+        }
+    }
+    Ok(changes)
+}
+
+/// Strip the managed hook block from each shell's rc files, leaving the rest untouched.
+pub fn remove_shells(shells: &[Shell]) -> Result<Vec<IntegrationChange>, String> {
+    let mut changes = Vec::new();
+    for &shell in shells {
+        for file in installed_files(shell)? {
+            let (action, backup) = remove_managed_block(&file, is_owned_file(shell))?;
+            changes.push(IntegrationChange {
+                integration: shell.name().to_string(),
+                file: file.to_string_lossy().to_string(),
+                action,
+                backup,
+            });
+        }
+    }
+    Ok(changes)
+}
+
+/// Rewrite every outdated managed block, in any rc file that has one, with
+/// the hook of this version calling the CLI at `cli`. Hand-edited blocks are
+/// reported as conflicts and kept unless `force` is set. Blocks written before
+/// checksums existed count as outdated.
+pub fn upgrade_integrations(force: bool, cli: &Path) -> Result<Vec<IntegrationChange>, String> {
+    let mut targets = Vec::new();
+    for &shell in Shell::ALL {
+        let block = managed_block(&generate_hook(shell, cli));
+        for file in installed_files(shell)? {
+            targets.push((shell.name().to_string(), file, block.clone()));
+        }
+    }
+    let direnv_lib = get_direnv_lib_path()?;
+    if direnv_lib.is_file() {
+        let block = managed_block(&generate_direnv_lib(cli));
+        targets.push(("direnv".to_string(), direnv_lib, block));
+    }
+
+    let mut changes = Vec::new();
+    for (integration, file, block) in targets {
+        let has_block = fs::read_to_string(&file)
+            .map(|contents| contents.contains(BLOCK_START))
+            .unwrap_or(false);
+        if !has_block {
+            continue;
+        }
+        let (action, backup) = write_managed_block(&file, &block, force)?;
+        changes.push(IntegrationChange {
+            integration,
+            file: file.to_string_lossy().to_string(),
+            action,
+            backup,
+        });
+    }
+    Ok(changes)
+}
+
+/// Files the hook for `shell` is installed into.
+///
+/// bash: `~/.bashrc`, plus `~/.bash_profile` when it exists and does not
+/// already source `~/.bashrc` (login shells such as macOS Terminal read only
+/// the profile).
+/// fish: a dedicated `conf.d/jdk-pulse.fish` snippet.
+/// nushell: `config.nu`; elvish: `rc.elv`; xonsh: `~/.xonshrc`;
+/// tcsh/csh: `~/.tcshrc` when it exists, else `~/.cshrc`.
+fn target_files(shell: Shell) -> Result<Vec<PathBuf>, String> {
+    let home = dirs::home_dir().ok_or("Could not determine home directory")?;
+    let files = match shell {
+        Shell::Zsh => {
+            let zdotdir = std::env::var_os("ZDOTDIR")
+                .map(PathBuf::from)
+                .unwrap_or_else(|| home.clone());
+            vec![zdotdir.join(".zshrc")]
+        }
+        Shell::Bash => {
+            let mut files = vec![home.join(".bashrc")];
+            let profile = home.join(".bash_profile");
+            if let Ok(contents) = fs::read_to_string(&profile) {
+                if !contents.contains(".bashrc") {
+                    files.push(profile);
+                }
+            }
+            files
+        }
+        Shell::Fish => vec![xdg_config_home(&home)
+            .join("fish")
+            .join("conf.d")
+            .join("jdk-pulse.fish")],
+        Shell::Nushell => {
+            // nushell honours XDG_CONFIG_HOME when set, else the platform config dir
+            let config_dir = match std::env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+                Some(dir) => PathBuf::from(dir),
+                None => dirs::config_dir().unwrap_or_else(|| home.join(".config")),
+            };
+            vec![config_dir.join("nushell").join("config.nu")]
+        }
+        Shell::Elvish => vec![xdg_config_home(&home).join("elvish").join("rc.elv")],
+        Shell::Xonsh => vec![home.join(".xonshrc")],
+        Shell::Tcsh => {
+            // tcsh reads ~/.tcshrc when it exists, otherwise ~/.cshrc (as csh does)
+            let tcshrc = home.join(".tcshrc");
+            if tcshrc.is_file() {
+                vec![tcshrc]
+            } else {
+                vec![home.join(".cshrc")]
+            }
+        }
+    };
+    Ok(files)
+}
+
+/// Whether `$SHELL` names `shell` (`csh` counts as tcsh).
+fn is_login_shell(shell: Shell) -> bool {
+    let Some(path) = std::env::var_os("SHELL") else {
+        return false;
+    };
+    let name = Path::new(&path).file_name().unwrap_or_default().to_string_lossy();
+    Shell::from_name(if name == "nu" { "nushell" } else { &name }) == Some(shell)
+}
+
+/// Whether `file` shows the user runs `shell`: the rc file exists, or for
+/// files jdk-pulse owns (fish's `conf.d` snippet), the shell's config directory.
+fn is_in_use(shell: Shell, file: &Path) -> bool {
+    if is_owned_file(shell) {
+        // <config>/fish/conf.d/jdk-pulse.fish -> <config>/fish
+        file.parent().and_then(Path::parent).is_some_and(Path::is_dir)
+    } else {
+        file.is_file()
+    }
+}
+
+/// `$XDG_CONFIG_HOME`, default `~/.config`. fish and elvish use it on every
+/// Unix platform, including macOS.
+pub(crate) fn xdg_config_home(home: &Path) -> PathBuf {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home.join(".config"))
+}
+
+/// Files created by jdk-pulse itself; they are deleted once their managed block is removed.
+fn is_owned_file(shell: Shell) -> bool {
+    shell == Shell::Fish
+}
+
+/// Files that may hold a managed block for `shell`, whether or not they are
+/// current install targets.
+fn installed_files(shell: Shell) -> Result<Vec<PathBuf>, String> {
+    let home = dirs::home_dir().ok_or("Could not determine home directory")?;
+    let mut files = target_files(shell)?;
+    let extra = match shell {
+        Shell::Bash => vec![home.join(".bash_profile")],
+        Shell::Tcsh => vec![home.join(".tcshrc"), home.join(".cshrc")],
+        _ => Vec::new(),
+    };
+    for file in extra {
+        if !files.contains(&file) {
+            files.push(file);
+        }
+    }
+    files.retain(|file| file.is_file());
+    Ok(files)
+}
+
+pub(crate) fn managed_block(body: &str) -> String {
+    let body = body.trim_end();
+    format!(
+        "{}\n{}{}{}{:016x}\n{}\n{}\n",
+        BLOCK_START,
+        BLOCK_META_PREFIX,
+        env!("CARGO_PKG_VERSION"),
+        BLOCK_META_CHECKSUM,
+        checksum(body),
+        body,
+        BLOCK_END
+    )
+}
+
+/// Whether a managed block's body no longer matches its recorded checksum.
+/// Blocks without a metadata line cannot be verified and count as unedited.
+fn is_hand_edited(block: &str) -> bool {
+    let inner = block
+        .trim_end()
+        .strip_prefix(BLOCK_START)
+        .and_then(|rest| rest.strip_suffix(BLOCK_END))
+        .unwrap_or("")
+        .trim_start_matches('\n');
+    let (meta, body) = inner.split_once('\n').unwrap_or((inner, ""));
+    let recorded = match meta
+        .strip_prefix(BLOCK_META_PREFIX)
+        .and_then(|meta| meta.split_once(BLOCK_META_CHECKSUM))
+    {
+        Some((_, recorded)) => recorded.trim(),
+        None => return false,
+    };
+    recorded != format!("{:016x}", checksum(body.trim_end()))
+}
+
+/// 64-bit FNV-1a; detects edits, not tampering.
+fn checksum(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// Insert `block` into `path`, replacing an existing managed block in place.
+/// Idempotent: an identical block leaves the file (and its mtime) untouched.
+/// A hand-edited block, or a start marker whose end marker is missing, is not
+/// replaced (`IntegrationAction::Conflict`).
+pub(crate) fn upsert_managed_block(
+    path: &Path,
+    block: &str,
+) -> Result<(IntegrationAction, Option<String>), String> {
+    write_managed_block(path, block, false)
+}
+
+fn write_managed_block(
+    path: &Path,
+    block: &str,
+    force: bool,
+) -> Result<(IntegrationAction, Option<String>), String> {
+    let existing = fs::read_to_string(path).unwrap_or_default();
+
+    let (updated, action) = match find_managed_block(&existing) {
+        Some((start, end)) => {
+            if &existing[start..end] == block {
+                return Ok((IntegrationAction::Unchanged, None));
+            }
+            if !force && is_hand_edited(&existing[start..end]) {
+                return Ok((IntegrationAction::Conflict, None));
+            }
+            let mut updated = existing.clone();
+            updated.replace_range(start..end, block);
+            (updated, IntegrationAction::Updated)
+        }
+        // Where the block ends is unknown, so neither replacing nor appending is safe
+        None if existing.contains(BLOCK_START) => return Ok((IntegrationAction::Conflict, None)),
+        None => {
+            let mut updated = existing.clone();
+            if !updated.is_empty() {
+                if !updated.ends_with('\n') {
+                    updated.push('\n');
+                }
+                updated.push('\n');
+            }
+            updated.push_str(block);
+            (updated, IntegrationAction::Installed)
+        }
+    };
+
+    let backup = backup_file(path)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Error creating {}: {e}", parent.display()))?;
+    }
+    fs::write(path, updated).map_err(|e| format!("Error writing {}: {e}", path.display()))?;
+    Ok((action, backup))
+}
+
+/// Remove the managed block from `path`, along with the blank line that
+/// `upsert_managed_block` put in front of it. With `delete_if_empty`, a file
+/// left with nothing but whitespace is deleted (used for files jdk-pulse owns).
+pub(crate) fn remove_managed_block(
+    path: &Path,
+    delete_if_empty: bool,
+) -> Result<(IntegrationAction, Option<String>), String> {
+    let existing = match fs::read_to_string(path) {
+        Ok(existing) => existing,
+        Err(_) => return Ok((IntegrationAction::Unchanged, None)),
+    };
+    let (mut start, end) = match find_managed_block(&existing) {
+        Some(range) => range,
+        None if existing.contains(BLOCK_START) => return Ok((IntegrationAction::Conflict, None)),
+        None => return Ok((IntegrationAction::Unchanged, None)),
+    };
+    if existing[..start].ends_with("\n\n") {
+        start -= 1;
+    }
+
+    let mut updated = existing.clone();
+    updated.replace_range(start..end, "");
+
+    if delete_if_empty && updated.trim().is_empty() {
+        fs::remove_file(path).map_err(|e| format!("Error removing {}: {e}", path.display()))?;
+        return Ok((IntegrationAction::Removed, None));
+    }
+
+    let backup = backup_file(path)?;
+    fs::write(path, updated).map_err(|e| format!("Error writing {}: {e}", path.display()))?;
+    Ok((IntegrationAction::Removed, backup))
+}
+
+/// Byte range of the managed block, from the start marker through the end
+/// marker's trailing newline.
+fn find_managed_block(contents: &str) -> Option<(usize, usize)> {
+    let start = contents.find(BLOCK_START)?;
+    let end_marker = start + contents[start..].find(BLOCK_END)?;
+    let mut end = end_marker + BLOCK_END.len();
+    if contents[end..].starts_with('\n') {
+        end += 1;
+    }
+    Some((start, end))
+}
+
+/// Copy `path` to `<path>.jdk-pulse.bak` before it is modified, replacing
+/// the backup of the previous change.
+fn backup_file(path: &Path) -> Result<Option<String>, String> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(".jdk-pulse.bak");
+    let backup = PathBuf::from(backup);
+
+    fs::copy(path, &backup)
+        .map_err(|e| format!("Error backing up {}: {e}", path.display()))?;
+    Ok(Some(backup.to_string_lossy().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        managed_block, remove_managed_block, upsert_managed_block, IntegrationAction, BLOCK_START,
+    };
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// Empty scratch directory for one test.
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("jdk-pulse-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn start_marker_without_end_marker_is_a_conflict() {
+        let dir = scratch_dir("unterminated");
+        let rc = dir.join(".bashrc");
+        let contents = format!("alias ll='ls -l'\n{}\n# jdk-pulse 0.1.0\necho half a block\n", BLOCK_START);
+        fs::write(&rc, &contents).unwrap();
+
+        let (action, backup) = upsert_managed_block(&rc, &managed_block("echo hook")).unwrap();
+        assert_eq!(action, IntegrationAction::Conflict);
+        assert_eq!(backup, None);
+        let (action, _) = remove_managed_block(&rc, false).unwrap();
+        assert_eq!(action, IntegrationAction::Conflict);
+        assert_eq!(fs::read_to_string(&rc).unwrap(), contents);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn repeated_writes_keep_a_single_backup() {
+        let dir = scratch_dir("backup");
+        let rc = dir.join(".zshrc");
+        fs::write(&rc, "export EDITOR=vi\n").unwrap();
+
+        upsert_managed_block(&rc, &managed_block("echo one")).unwrap();
+        upsert_managed_block(&rc, &managed_block("echo two")).unwrap();
+        let (action, backup) = remove_managed_block(&rc, false).unwrap();
+        assert_eq!(action, IntegrationAction::Removed);
+
+        let backups: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .flatten()
+            .filter(|entry| entry.file_name() != ".zshrc")
+            .collect();
+        assert_eq!(backups.len(), 1);
+        let backup = backup.expect("file was backed up");
+        assert!(fs::read_to_string(backup).unwrap().contains("echo two"));
+        assert_eq!(fs::read_to_string(&rc).unwrap(), "export EDITOR=vi\n");
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,9 +1,31 @@
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
+pub mod activation;
+pub mod buildfile;
+pub mod completions;
+pub mod config;
+pub mod daemons;
+pub mod desktop;
+pub mod direnv;
+pub mod doctor;
+pub mod history;
+pub mod hooks;
+pub mod hsperf;
+pub mod ide;
+pub mod integration;
+pub mod multiplexers;
+pub mod processes;
+pub mod profiles;
+pub mod project;
+pub mod session;
+pub mod shadowing;
+pub mod shims;
+pub mod spec;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JdkInfo {
     pub id: String,
@@ -13,8 +35,80 @@ pub struct JdkInfo {
     pub vendor: Option<String>,
 }
 
-#[cfg(target_os = "macos")]
+/// List installed JDKs, honouring the config file: JDKs under extra
+/// `scan_roots` are added and `hidden` IDs are left out.
 pub fn list_jdks() -> Result<Vec<JdkInfo>, String> {
+    let config = config::load_config_or_default();
+    let mut all = list_platform_jdks()?;
+
+    for root in config.scan_root_paths() {
+        for jdk in scan_jdk_root(&root) {
+            if !all.iter().any(|j| j.home == jdk.home) {
+                all.push(jdk);
+            }
+        }
+    }
+
+    all.retain(|jdk| !config.hidden.contains(&jdk.id));
+    write_jdk_cache(&all);
+    Ok(all)
+}
+
+/// JDKs from the discovery cache written by the last `list_jdks`, for hot paths
+/// such as shims. Falls back to a full discovery when `read_jdk_cache` has nothing.
+pub fn cached_jdks() -> Result<Vec<JdkInfo>, String> {
+    match read_jdk_cache() {
+        Some(jdks) => Ok(jdks),
+        None => list_jdks(),
+    }
+}
+
+/// The discovery cache, unless it is missing, older than the config file, or
+/// names a home that no longer exists.
+pub(crate) fn read_jdk_cache() -> Option<Vec<JdkInfo>> {
+    let cache = get_jdk_cache_path();
+    if !jdk_cache_is_fresh(&cache) {
+        return None;
+    }
+    let jdks = serde_json::from_str::<Vec<JdkInfo>>(&fs::read_to_string(&cache).ok()?).ok()?;
+    jdks.iter().all(|jdk| Path::new(&jdk.home).is_dir()).then_some(jdks)
+}
+
+fn jdk_cache_is_fresh(cache: &Path) -> bool {
+    let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+    match (modified(cache), modified(&config::get_config_file_path())) {
+        (Some(cached), Some(configured)) => cached >= configured,
+        (Some(_), None) => true,
+        _ => false,
+    }
+}
+
+/// Write the cache and `jdk-homes`, leaving files whose contents did not
+/// change alone, so a rescan that finds nothing new writes nothing. A cache
+/// older than the config is always rewritten to mark it fresh again.
+fn write_jdk_cache(jdks: &[JdkInfo]) {
+    let cache = get_jdk_cache_path();
+    if let Some(parent) = cache.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let unchanged = |path: &Path, contents: &str| fs::read_to_string(path).is_ok_and(|old| old == contents);
+    if let Ok(json) = serde_json::to_string(jdks) {
+        if !(jdk_cache_is_fresh(&cache) && unchanged(&cache, &json)) {
+            if let Err(e) = fs::write(&cache, json) {
+                eprintln!("Warning: Failed to write JDK cache {}: {e}", cache.display());
+            }
+        }
+    }
+    // Plain list for the shell hooks, which strip these homes from PATH
+    let homes: String = jdks.iter().map(|jdk| format!("{}\n", jdk.home)).collect();
+    let homes_file = get_jdk_homes_file_path();
+    if !unchanged(&homes_file, &homes) {
+        let _ = fs::write(homes_file, homes);
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn list_platform_jdks() -> Result<Vec<JdkInfo>, String> {
     let mut all = Vec::new();
 
     // System JDKs from java_home
@@ -27,10 +121,84 @@ pub fn list_jdks() -> Result<Vec<JdkInfo>, String> {
 }
 
 #[cfg(not(target_os = "macos"))]
-pub fn list_jdks() -> Result<Vec<JdkInfo>, String> {
+fn list_platform_jdks() -> Result<Vec<JdkInfo>, String> {
     Ok(vec![])
 }
 
+/// Find JDK homes in `root`: the directory itself or its immediate children,
+/// including macOS bundles (`<name>.jdk/Contents/Home`). A home needs
+/// `bin/java` and must be a JDK root (see `activation::is_jdk_root`).
+fn scan_jdk_root(root: &Path) -> Vec<JdkInfo> {
+    let mut candidates = vec![root.to_path_buf()];
+    if let Ok(entries) = fs::read_dir(root) {
+        let mut children: Vec<PathBuf> = entries.flatten().map(|e| e.path()).collect();
+        children.sort();
+        candidates.extend(children);
+    }
+
+    let mut result = Vec::new();
+    for candidate in candidates {
+        let contents_home = candidate.join("Contents").join("Home");
+        let home_path = if contents_home.is_dir() {
+            contents_home
+        } else {
+            candidate.clone()
+        };
+        // bin/java alone is not enough: /usr and /usr/local have one too
+        if !home_path.join("bin").join("java").exists()
+            || !activation::is_jdk_root(&home_path.to_string_lossy())
+        {
+            continue;
+        }
+
+        let name = match candidate.file_name().and_then(|s| s.to_str()) {
+            Some(name) => name.to_string(),
+            None => continue,
+        };
+        let (release_version, implementor) = read_release_file(&home_path);
+        let version_full = release_version.unwrap_or_else(|| {
+            // Fall back to the first version-looking part of the directory name
+            name.split(['-', '_'])
+                .find(|part| part.starts_with(|c: char| c.is_ascii_digit()))
+                .unwrap_or("unknown")
+                .to_string()
+        });
+
+        result.push(JdkInfo {
+            id: name.trim_end_matches(".jdk").replace('.', "_"),
+            version_major: parse_major_version(&version_full),
+            version_full,
+            home: home_path.to_string_lossy().to_string(),
+            vendor: implementor,
+        });
+    }
+
+    result
+}
+
+/// `JAVA_VERSION` and `IMPLEMENTOR` from the `release` file of a JDK home.
+fn read_release_file(home: &Path) -> (Option<String>, Option<String>) {
+    let contents = match fs::read_to_string(home.join("release")) {
+        Ok(contents) => contents,
+        Err(_) => return (None, None),
+    };
+
+    let mut version = None;
+    let mut implementor = None;
+    for line in contents.lines() {
+        if let Some((key, value)) = line.split_once('=') {
+            let value = value.trim().trim_matches('"').to_string();
+            match key.trim() {
+                "JAVA_VERSION" => version = Some(value),
+                "IMPLEMENTOR" => implementor = Some(value),
+                _ => {}
+            }
+        }
+    }
+
+    (version, implementor)
+}
+
 #[cfg(target_os = "macos")]
 fn list_system_jdks_macos() -> Result<Vec<JdkInfo>, String> {
     let output = Command::new("/usr/libexec/java_home")
@@ -130,7 +298,10 @@ fn list_jenv_jdks() -> Result<Vec<JdkInfo>, String> {
         };
 
         // Require bin/java to exist
-        if !home_path.join("bin").join("java").exists() {
+        // bin/java alone is not enough: /usr and /usr/local have one too
+        if !home_path.join("bin").join("java").exists()
+            || !activation::is_jdk_root(&home_path.to_string_lossy())
+        {
             continue;
         }
 
@@ -243,13 +414,7 @@ pub fn get_active_jdk() -> Result<Option<JdkInfo>, String> {
                             Ok(Some(jdk.clone()))
                         } else {
                             // Return a minimal JdkInfo with just the home path
-                            Ok(Some(JdkInfo {
-                                id: "unknown".to_string(),
-                                version_major: 0,
-                                version_full: "unknown".to_string(),
-                                home: home.to_string(),
-                                vendor: None,
-                            }))
+                            Ok(Some(unknown_jdk_info(home)))
                         }
                     }
                     Err(e) => Err(e),
@@ -261,31 +426,20 @@ pub fn get_active_jdk() -> Result<Option<JdkInfo>, String> {
 }
 
 pub fn set_active_jdk(id_or_home: &str) -> Result<String, String> {
-    let jdk_home = if id_or_home.starts_with('/') || id_or_home.starts_with("~/") {
-        // It's a path
-        let mut path = PathBuf::from(id_or_home);
-        if id_or_home.starts_with("~/") {
-            if let Some(home) = dirs::home_dir() {
-                path = home.join(&id_or_home[2..]);
-            }
-        }
-        if !path.exists() {
-            return Err(format!("JDK path does not exist: {}", path.display()));
-        }
-        path.to_string_lossy().to_string()
-    } else {
-        // It's an ID - find the matching JDK
-        match list_jdks() {
-            Ok(jdks) => {
-                if let Some(jdk) = jdks.iter().find(|j| j.id == id_or_home) {
-                    jdk.home.clone()
-                } else {
-                    return Err(format!("JDK with ID '{}' not found", id_or_home));
-                }
-            }
-            Err(e) => return Err(e),
-        }
-    };
+    switch_jdk(id_or_home, true, None)
+}
+
+/// `set_active_jdk`, optionally without recording the switch in the history
+/// (for `history::go_back` and `go_forward`, which move its cursor instead).
+/// With a profile, its variables are published along with the JDK
+/// (`profiles::activate_profile`); without one, the previous profile's go.
+pub(crate) fn switch_jdk(
+    id_or_home: &str,
+    record_history: bool,
+    profile: Option<&profiles::Profile>,
+) -> Result<String, String> {
+    let config = config::load_config_or_default();
+    let jdk_home = resolve_jdk_with(id_or_home, &list_jdks()?, &config)?.home;
 
     // Validate the JDK home path
     let jdk_path = PathBuf::from(&jdk_home);
@@ -302,6 +456,10 @@ pub fn set_active_jdk(id_or_home: &str) -> Result<String, String> {
 
     // Write to state file
     let state_file = get_state_file_path();
+    let previous_home = fs::read_to_string(&state_file)
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
     if let Some(parent) = state_file.parent() {
         fs::create_dir_all(parent)
             .map_err(|e| format!("Error creating state directory: {e}"))?;
@@ -312,9 +470,96 @@ pub fn set_active_jdk(id_or_home: &str) -> Result<String, String> {
     file.write_all(jdk_home.as_bytes())
         .map_err(|e| format!("Error writing state file: {e}"))?;
 
+    // The new profile's variables replace the previous profile's everywhere
+    let previous_env = profiles::active_profile_env();
+    let profile_env = profile.map(|profile| profile.env.clone()).unwrap_or_default();
+    let stale_vars: Vec<String> = previous_env
+        .into_keys()
+        .filter(|key| !profile_env.contains_key(key))
+        .collect();
+    match profile {
+        Some(profile) => profiles::write_profile_env(profile)?,
+        None => profiles::clear_profile_env(),
+    }
+
+    if record_history && previous_home.as_deref() != Some(jdk_home.as_str()) {
+        if let Err(e) = history::record_activation(previous_home.as_deref(), &jdk_home) {
+            eprintln!("Warning: could not record JDK history: {e}");
+        }
+    }
+
+    if config.integrations.desktop {
+        if let Err(e) = desktop::sync_desktop_environment(&jdk_home, &profile_env, &stale_vars) {
+            eprintln!("Warning: could not update the desktop environment: {e}");
+        }
+    }
+
+    if config.integrations.multiplexers {
+        if let Err(e) = multiplexers::sync_multiplexers(&jdk_home, &profile_env, &stale_vars) {
+            eprintln!("Warning: could not update tmux/screen: {e}");
+        }
+    }
+
+    if let Err(e) = ide::sync_ide_settings(&config.integrations.ides, &jdk_home) {
+        eprintln!("Warning: could not update IDE settings: {e}");
+    }
+
     Ok(jdk_home)
 }
 
+/// Resolve a JDK spec (ID, home path, or version such as `17` / `temurin-21`)
+/// without changing the active selection.
+pub fn resolve_jdk(spec: &str) -> Result<JdkInfo, String> {
+    let config = config::load_config_or_default();
+    resolve_jdk_with(spec, &list_jdks()?, &config)
+}
+
+/// `resolve_jdk` against the given JDK list. Config aliases are followed here
+/// and nowhere else.
+pub(crate) fn resolve_jdk_with(
+    spec: &str,
+    jdks: &[JdkInfo],
+    config: &config::Config,
+) -> Result<JdkInfo, String> {
+    let spec = config.resolve_alias(spec.trim());
+
+    if spec.starts_with('/') || spec.starts_with("~/") {
+        let path = expand_home(spec);
+        if !path.exists() {
+            return Err(format!("JDK path does not exist: {}", path.display()));
+        }
+        let home = path.to_string_lossy().to_string();
+        return Ok(jdks
+            .iter()
+            .find(|j| j.home == home)
+            .cloned()
+            .unwrap_or_else(|| unknown_jdk_info(&home)));
+    }
+
+    spec::find_jdk_by_spec(spec, jdks, config)
+        .cloned()
+        .ok_or_else(|| format!("No installed JDK matches '{}'", spec))
+}
+
+/// Expand a leading `~/` to the user's home directory.
+pub(crate) fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Minimal JdkInfo for a home that discovery does not know about.
+fn unknown_jdk_info(home: &str) -> JdkInfo {
+    JdkInfo {
+        id: "unknown".to_string(),
+        version_major: 0,
+        version_full: "unknown".to_string(),
+        home: home.to_string(),
+        vendor: None,
+    }
+}
+
 fn get_state_file_path() -> PathBuf {
     if let Some(home) = dirs::home_dir() {
         home.join(".jdk_current")
@@ -323,9 +568,37 @@ fn get_state_file_path() -> PathBuf {
     }
 }
 
+/// Environment published by the active profile, read by the shell hooks next to `~/.jdk_current`.
+fn get_profile_env_file_path() -> PathBuf {
+    let mut path = get_state_file_path().into_os_string();
+    path.push(".env");
+    PathBuf::from(path)
+}
+
+/// Directory for app-internal state (history, caches), e.g. `~/.local/state/jdk-pulse`.
+fn get_state_dir_path() -> PathBuf {
+    match dirs::state_dir().or_else(dirs::data_local_dir) {
+        Some(dir) => dir.join("jdk-pulse"),
+        None => PathBuf::from(".jdk-pulse"),
+    }
+}
+
+/// Discovery cache, see `cached_jdks`.
+fn get_jdk_cache_path() -> PathBuf {
+    get_state_dir_path().join("jdks.json")
+}
+
+/// Known JDK homes, one per line, written with the discovery cache.
+fn get_jdk_homes_file_path() -> PathBuf {
+    get_state_dir_path().join("jdk-homes")
+}
+
 // Tauri commands
 #[cfg(feature = "tauri")]
 pub mod tauri_commands {
+    use super::doctor::{run_doctor_sync, DoctorReport};
+    use super::hooks::find_cli;
+    use super::integration::{install_shell_integration, remove_shell_integration, IntegrationChange};
     use super::{get_active_jdk, list_jdks, set_active_jdk, JdkInfo};
 
     #[tauri::command]
@@ -342,16 +615,45 @@ pub mod tauri_commands {
     pub async fn set_active_jdk_command(id: String) -> Result<String, String> {
         set_active_jdk(&id)
     }
+
+    /// The hooks must call the CLI, not this tray process.
+    #[tauri::command]
+    pub async fn install_shell_integration_command() -> Result<Vec<IntegrationChange>, String> {
+        install_shell_integration(&find_cli()?)
+    }
+
+    #[tauri::command]
+    pub async fn remove_shell_integration_command() -> Result<Vec<IntegrationChange>, String> {
+        remove_shell_integration()
+    }
+
+    #[tauri::command]
+    pub async fn run_doctor_sync_command() -> Result<DoctorReport, String> {
+        run_doctor_sync()
+    }
 }
 
 #[cfg(feature = "tauri")]
 pub mod tauri_tray {
     use super::{get_active_jdk, list_jdks, set_active_jdk};
+    use super::config::{get_config_file_path, load_config_or_default};
+    use super::daemons::{list_daemons, stop_stale_daemons, StopOutcome};
+    use super::hsperf::list_jvms;
+    use super::processes::find_stale_processes;
+    use super::profiles::{activate_profile, active_profile, list_profiles};
+    use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+    use std::sync::Mutex;
+    use std::time::{Duration, SystemTime};
     use tauri::{AppHandle, Manager};
-    use tauri::menu::MenuBuilder;
+    use tauri::menu::{MenuBuilder, MenuItemBuilder, SubmenuBuilder};
     use tauri::tray::{TrayIconBuilder, TrayIcon};
 
     pub fn create_system_tray<R: tauri::Runtime>(app: &AppHandle<R>) -> Result<TrayIcon<R>, Box<dyn std::error::Error>> {
+        let (rescan, rescan_requests) = channel();
+        app.manage(ProcessScan {
+            summary: Mutex::new(ProcessSummary::default()),
+            rescan,
+        });
         let menu = create_tray_menu(app)?;
         
         // Use default window icon if available, otherwise create without icon
@@ -370,27 +672,101 @@ pub mod tauri_tray {
                     "quit" => {
                         app.exit(0);
                     }
+                    "previous-jdk" => {
+                        match super::history::go_back() {
+                            Ok(home) => {
+                                println!("Active JDK restored to: {}", home);
+                                jdk_switched(app);
+                            }
+                            Err(e) => {
+                                eprintln!("Error restoring previous JDK: {e}");
+                            }
+                        }
+                    }
+                    "next-jdk" => {
+                        match super::history::go_forward() {
+                            Ok(home) => {
+                                println!("Active JDK restored to: {}", home);
+                                jdk_switched(app);
+                            }
+                            Err(e) => {
+                                eprintln!("Error restoring next JDK: {e}");
+                            }
+                        }
+                    }
                     "jenv-default" => {
                         match super::set_jenv_default_active() {
                             Ok(home) => {
                                 println!("Active JDK set to jenv default: {}", home);
-                                if let Err(e) = update_tray_menu(app) {
-                                    eprintln!("Error updating tray menu: {e}");
-                                }
+                                jdk_switched(app);
                             }
                             Err(e) => {
                                 eprintln!("Error setting jenv default JDK: {e}");
                             }
                         }
                     }
+                    "sync-ides" => {
+                        let config = load_config_or_default();
+                        match get_active_jdk() {
+                            Ok(Some(jdk)) => match super::ide::sync_ide_settings(&config.integrations.ides, &jdk.home) {
+                                Ok(written) => {
+                                    for path in written {
+                                        println!("Updated {}", path.display());
+                                    }
+                                }
+                                Err(e) => {
+                                    eprintln!("Error updating IDE settings: {e}");
+                                }
+                            },
+                            Ok(None) => println!("No active JDK to sync"),
+                            Err(e) => {
+                                eprintln!("Error getting active JDK: {e}");
+                            }
+                        }
+                    }
+                    "stop-stale-daemons" => {
+                        // Waits for the daemons to exit, so keep it off the main thread
+                        let app = app.clone();
+                        std::thread::spawn(move || {
+                            match stop_stale_daemons() {
+                                Ok(stopped) => {
+                                    for stop in stopped {
+                                        match stop.outcome {
+                                            StopOutcome::Failed => {
+                                                eprintln!("Error stopping {} {}", stop.daemon.kind.name(), stop.daemon.pid);
+                                            }
+                                            StopOutcome::Skipped => {
+                                                println!("Left {} {} running: unknown JDK", stop.daemon.kind.name(), stop.daemon.pid);
+                                            }
+                                            _ => println!("Stopped {} {}", stop.daemon.kind.name(), stop.daemon.pid),
+                                        }
+                                    }
+                                }
+                                Err(e) => {
+                                    eprintln!("Error stopping daemons: {e}");
+                                }
+                            }
+                            request_process_scan(&app);
+                        });
+                    }
+                    id if id.starts_with("profile:") => {
+                        let name = &id["profile:".len()..];
+                        match activate_profile(name) {
+                            Ok(home) => {
+                                println!("Profile '{}' activated: {}", name, home);
+                                jdk_switched(app);
+                            }
+                            Err(e) => {
+                                eprintln!("Error activating profile: {e}");
+                            }
+                        }
+                    }
                     id => {
                         // It's a JDK selection
                         match set_active_jdk(id) {
                             Ok(home) => {
                                 println!("Active JDK set to: {}", home);
-                                if let Err(e) = update_tray_menu(app) {
-                                    eprintln!("Error updating tray menu: {e}");
-                                }
+                                jdk_switched(app);
                             }
                             Err(e) => {
                                 eprintln!("Error setting JDK: {e}");
@@ -400,12 +776,132 @@ pub mod tauri_tray {
                 }
             })
             .build(app)?;
-        
+
+        watch_config_file(app.clone());
+        watch_processes(app.clone(), rescan_requests);
+
         Ok(tray)
     }
 
+    /// Refresh the menu after a JDK switch and rescan processes, since the
+    /// switch changes which of them count as stale.
+    fn jdk_switched<R: tauri::Runtime>(app: &AppHandle<R>) {
+        if let Err(e) = update_tray_menu(app) {
+            eprintln!("Error updating tray menu: {e}");
+        }
+        request_process_scan(app);
+    }
+
+    /// Rebuild the menu on the main thread, from a background thread.
+    fn schedule_tray_menu_update<R: tauri::Runtime>(app: &AppHandle<R>) {
+        let handle = app.clone();
+        let result = app.run_on_main_thread(move || {
+            if let Err(e) = update_tray_menu(&handle) {
+                eprintln!("Error updating tray menu: {e}");
+            }
+        });
+        if let Err(e) = result {
+            eprintln!("Error scheduling tray menu update: {e}");
+        }
+    }
+
+    /// How often the config file is checked for edits
+    const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+    /// Rebuild the menu whenever the config file changes, so edits to aliases,
+    /// hidden JDKs or scan roots show up without restarting the tray.
+    fn watch_config_file<R: tauri::Runtime>(app: AppHandle<R>) {
+        std::thread::spawn(move || {
+            let path = get_config_file_path();
+            let modified = || -> Option<SystemTime> { std::fs::metadata(&path).ok()?.modified().ok() };
+            let mut last_modified = modified();
+
+            loop {
+                std::thread::sleep(CONFIG_POLL_INTERVAL);
+                let current = modified();
+                if current == last_modified {
+                    continue;
+                }
+                last_modified = current;
+                schedule_tray_menu_update(&app);
+            }
+        });
+    }
+
+    /// How often running JVMs, daemons and stale processes are rescanned
+    const PROCESS_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+    /// What the menu shows about running processes. Walking /proc and the
+    /// perf-data files is too slow for the main thread, so `watch_processes`
+    /// keeps this up to date and the menu only reads it.
+    #[derive(Debug, Clone, Default, PartialEq)]
+    struct ProcessSummary {
+        /// `(pid, label)` of each running JVM
+        jvms: Vec<(u32, String)>,
+        stale_daemons: usize,
+        stale_processes: usize,
+    }
+
+    /// Tray state: the last process scan and a way to ask for the next one early.
+    struct ProcessScan {
+        summary: Mutex<ProcessSummary>,
+        rescan: Sender<()>,
+    }
+
+    fn request_process_scan<R: tauri::Runtime>(app: &AppHandle<R>) {
+        if let Some(scan) = app.try_state::<ProcessScan>() {
+            let _ = scan.rescan.send(());
+        }
+    }
+
+    /// Rescan processes every `PROCESS_POLL_INTERVAL`, or sooner when asked,
+    /// and rebuild the menu when the summary changed.
+    fn watch_processes<R: tauri::Runtime>(app: AppHandle<R>, requests: Receiver<()>) {
+        std::thread::spawn(move || loop {
+            let summary = scan_processes();
+            let changed = app.try_state::<ProcessScan>().is_some_and(|scan| {
+                let mut current = scan.summary.lock().unwrap_or_else(|e| e.into_inner());
+                let changed = *current != summary;
+                *current = summary;
+                changed
+            });
+            if changed {
+                schedule_tray_menu_update(&app);
+            }
+
+            match requests.recv_timeout(PROCESS_POLL_INTERVAL) {
+                Err(RecvTimeoutError::Disconnected) => break,
+                // Requests that piled up during the scan are covered by the next one
+                _ => while requests.try_recv().is_ok() {},
+            }
+        });
+    }
+
+    fn scan_processes() -> ProcessSummary {
+        let jvms = list_jvms()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|jvm| {
+                let version = match jvm.version_major {
+                    Some(major) => format!("Java {}", major),
+                    None => "unknown JDK".to_string(),
+                };
+                let label = format!("{} {} – {}", jvm.pid, jvm.main_class().unwrap_or("?"), version);
+                (jvm.pid, label)
+            })
+            .collect();
+        ProcessSummary {
+            jvms,
+            stale_daemons: list_daemons()
+                .map(|daemons| daemons.iter().filter(|daemon| daemon.is_stale()).count())
+                .unwrap_or(0),
+            stale_processes: find_stale_processes().map(|stale| stale.len()).unwrap_or(0),
+        }
+    }
+
     fn create_tray_menu<R: tauri::Runtime>(app: &AppHandle<R>) -> Result<tauri::menu::Menu<R>, Box<dyn std::error::Error>> {
         let mut builder = MenuBuilder::new(app);
+        let config = load_config_or_default();
 
         match (list_jdks(), get_active_jdk()) {
             (Ok(jdks), Ok(active_jdk)) => {
@@ -416,6 +912,23 @@ pub mod tauri_tray {
                     builder = builder.separator();
                 }
 
+                // "Previous JDK" undoes the last switch, "Next JDK" redoes an undone one
+                if let Ok(history) = super::history::load_history() {
+                    let label = |action: &str, home: &str| match jdks.iter().find(|j| j.home == home) {
+                        Some(jdk) => format!("{} (Java {})", action, jdk.version_major),
+                        None => action.to_string(),
+                    };
+                    if let Some(home) = history.previous() {
+                        builder = builder.text("previous-jdk", label("Previous JDK", home));
+                    }
+                    if let Some(home) = history.next() {
+                        builder = builder.text("next-jdk", label("Next JDK", home));
+                    }
+                    if history.previous().is_some() || history.next().is_some() {
+                        builder = builder.separator();
+                    }
+                }
+
                 for jdk in jdks {
                     let label = match &jdk.vendor {
                         Some(vendor) if vendor == "jenv" => {
@@ -424,6 +937,12 @@ pub mod tauri_tray {
                         Some(vendor) => format!("Java {} ({})", jdk.version_major, vendor),
                         None => format!("Java {}", jdk.version_major),
                     };
+                    let aliases = config.aliases_for(&jdk.id, &jdk.home);
+                    let label = if aliases.is_empty() {
+                        label
+                    } else {
+                        format!("{} – {}", label, aliases.join(", "))
+                    };
 
                     let is_active = active_jdk
                         .as_ref()
@@ -444,6 +963,75 @@ pub mod tauri_tray {
             }
         }
 
+        // Profiles submenu (only when profiles are configured)
+        if let Ok(profiles) = list_profiles() {
+            if !profiles.is_empty() {
+                let active = active_profile();
+                let mut submenu = SubmenuBuilder::new(app, "Profiles");
+                for profile in profiles {
+                    let text = if active.as_deref() == Some(profile.name.as_str()) {
+                        format!("✓ {}", profile.name)
+                    } else {
+                        profile.name.clone()
+                    };
+                    submenu = submenu.text(format!("profile:{}", profile.name), text);
+                }
+                builder = builder.separator();
+                builder = builder.item(&submenu.build()?);
+            }
+        }
+
+        // Process entries come from the last background scan
+        let summary = app
+            .try_state::<ProcessScan>()
+            .map(|scan| {
+                let summary = scan.summary.lock().unwrap_or_else(|e| e.into_inner());
+                summary.clone()
+            })
+            .unwrap_or_default();
+
+        // Running JVMs, from their perf-data files
+        if !summary.jvms.is_empty() {
+            let mut submenu = SubmenuBuilder::new(app, format!("Running JVMs ({})", summary.jvms.len()));
+            for (pid, label) in &summary.jvms {
+                let item = MenuItemBuilder::with_id(format!("jvm:{}", pid), label)
+                    .enabled(false)
+                    .build(app)?;
+                submenu = submenu.item(&item);
+            }
+            builder = builder.separator();
+            builder = builder.item(&submenu.build()?);
+        }
+
+        // Build daemons and language servers left on a previous JDK
+        if summary.stale_daemons > 0 {
+            let label = match summary.stale_daemons {
+                1 => "Stop 1 daemon on old JDK".to_string(),
+                n => format!("Stop {} daemons on old JDK", n),
+            };
+            builder = builder.separator();
+            builder = builder.text("stop-stale-daemons", label);
+        }
+
+        // Shells and JVMs still on a previous JDK (Linux only)
+        if summary.stale_processes > 0 {
+            let label = match summary.stale_processes {
+                1 => "1 process on old JDK".to_string(),
+                n => format!("{} processes on old JDK", n),
+            };
+            let item = MenuItemBuilder::with_id("stale-processes", label)
+                .enabled(false)
+                .build(app)?;
+            builder = builder.separator();
+            builder = builder.item(&item);
+        }
+
+        // Re-apply the active JDK to enabled IDEs
+        if !config.integrations.ides.is_empty() {
+            builder = builder.separator();
+            builder = builder.text("sync-ides", "Sync IDE settings");
+        }
+
         // Add separator
         builder = builder.separator();
 
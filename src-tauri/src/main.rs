@@ -1,3 +1,22 @@
+use jdk_pulse::activation::{jdk_environment, render_environment, EnvFormat};
+use jdk_pulse::completions::{completion_candidates, generate_completions};
+use jdk_pulse::config::{get_config_file_path, load_config, validate_config};
+use jdk_pulse::daemons::{list_daemons, stop_stale_daemons};
+use jdk_pulse::desktop::{remove_desktop_integration, sync_desktop_environment};
+use jdk_pulse::doctor::{run_doctor_sync, CheckStatus};
+use jdk_pulse::direnv::{generate_direnv_lib, install_direnv_integration, remove_direnv_integration};
+use jdk_pulse::hooks::{generate_hook, Shell};
+use jdk_pulse::hsperf::list_jvms;
+use jdk_pulse::integration::{
+    install_shell_integration, install_shells, remove_shell_integration, remove_shells, upgrade_integrations,
+    IntegrationAction,
+};
+use jdk_pulse::history::{go_back, go_forward, load_history};
+use jdk_pulse::multiplexers::sync_multiplexers;
+use jdk_pulse::project::{effective_jdk, find_project_jdk};
+use jdk_pulse::shims::{get_shims_dir_path, install_shims, remove_shims};
+use jdk_pulse::session::{clear_session_jdk, get_session_state, set_session_jdk};
+use jdk_pulse::profiles::{activate_profile, active_profile_env, list_profiles, remove_profile, save_profile, Profile};
 use jdk_pulse::{get_active_jdk, list_jdks, set_active_jdk};
 use serde_json;
 
@@ -17,9 +36,9 @@ fn main() {
                     }
                 }
             }
-            "--set" | "-s" => {
+            "--set" | "-s" | "use" => {
                 if args.len() < 3 {
-                    eprintln!("Usage: {} --set <id|home>", args[0]);
+                    eprintln!("Usage: {} --set|use <id|home|version>", args[0]);
                     std::process::exit(1);
                 }
                 match set_active_jdk(&args[2]) {
@@ -46,12 +65,531 @@ fn main() {
                     }
                 }
             }
+            "back" => {
+                match go_back() {
+                    Ok(home) => {
+                        println!("Active JDK restored to: {}", home);
+                    }
+                    Err(e) => {
+                        eprintln!("Error: {e}");
+                        std::process::exit(1);
+                    }
+                }
+            }
+            "forward" => {
+                match go_forward() {
+                    Ok(home) => {
+                        println!("Active JDK restored to: {}", home);
+                    }
+                    Err(e) => {
+                        eprintln!("Error: {e}");
+                        std::process::exit(1);
+                    }
+                }
+            }
+            "history" => {
+                match load_history() {
+                    Ok(history) => {
+                        println!("{}", serde_json::to_string_pretty(&history).unwrap());
+                    }
+                    Err(e) => {
+                        eprintln!("Error: {e}");
+                        std::process::exit(1);
+                    }
+                }
+            }
+            "profile" => {
+                let result = match (args.get(2).map(|s| s.as_str()), args.get(3)) {
+                    (None | Some("list"), _) => list_profiles().map(|profiles| {
+                        println!("{}", serde_json::to_string_pretty(&profiles).unwrap());
+                    }),
+                    (Some("use"), Some(name)) => activate_profile(name).map(|home| {
+                        println!("Profile '{}' activated: {}", name, home);
+                    }),
+                    (Some("add"), Some(name)) if args.len() >= 5 => {
+                        let mut env = std::collections::BTreeMap::new();
+                        for pair in &args[5..] {
+                            match pair.split_once('=') {
+                                Some((key, value)) => {
+                                    env.insert(key.to_string(), value.to_string());
+                                }
+                                None => {
+                                    eprintln!("Expected KEY=VALUE, got: {}", pair);
+                                    std::process::exit(1);
+                                }
+                            }
+                        }
+                        let profile = Profile {
+                            name: name.clone(),
+                            jdk: args[4].clone(),
+                            env,
+                        };
+                        save_profile(&profile).map(|_| {
+                            println!("Profile '{}' saved", name);
+                        })
+                    }
+                    (Some("remove"), Some(name)) => remove_profile(name).map(|_| {
+                        println!("Profile '{}' removed", name);
+                    }),
+                    _ => {
+                        eprintln!("Usage:");
+                        eprintln!("  {} profile [list]", args[0]);
+                        eprintln!("  {} profile use <name>", args[0]);
+                        eprintln!("  {} profile add <name> <jdk> [KEY=VALUE...]", args[0]);
+                        eprintln!("  {} profile remove <name>", args[0]);
+                        std::process::exit(1);
+                    }
+                };
+                if let Err(e) = result {
+                    eprintln!("Error: {e}");
+                    std::process::exit(1);
+                }
+            }
+            "project" | "home" => {
+                // `home --project` prints only a project-pinned JAVA_HOME (used by the shell hooks)
+                let project_only = args[1] == "home" && args.get(2).map(|s| s.as_str()) == Some("--project");
+                let dir_arg = if project_only { args.get(3) } else { args.get(2) };
+                let dir = match dir_arg {
+                    Some(dir) => std::path::PathBuf::from(dir),
+                    None => std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from(".")),
+                };
+                if args[1] == "project" {
+                    match find_project_jdk(&dir) {
+                        Ok(Some(project)) => {
+                            println!("{}", serde_json::to_string_pretty(&project).unwrap());
+                        }
+                        Ok(None) => {
+                            println!("{{}}");
+                        }
+                        Err(e) => {
+                            eprintln!("Error: {e}");
+                            std::process::exit(1);
+                        }
+                    }
+                } else if project_only {
+                    match find_project_jdk(&dir) {
+                        Ok(Some(project)) => match project.jdk {
+                            Some(jdk) => {
+                                println!("{}", jdk.home);
+                            }
+                            None => {
+                                eprintln!(
+                                    "Error: {} requires JDK '{}', but no installed JDK matches",
+                                    project.source, project.spec
+                                );
+                                std::process::exit(1);
+                            }
+                        },
+                        Ok(None) => {
+                            std::process::exit(1);
+                        }
+                        Err(e) => {
+                            eprintln!("Error: {e}");
+                            std::process::exit(1);
+                        }
+                    }
+                } else {
+                    // Plain JAVA_HOME output for shell hooks and scripts
+                    match effective_jdk(&dir) {
+                        Ok(Some(effective)) => {
+                            println!("{}", effective.home);
+                        }
+                        Ok(None) => {
+                            std::process::exit(1);
+                        }
+                        Err(e) => {
+                            eprintln!("Error: {e}");
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
+            "session" => {
+                let result = match (args.get(2).map(|s| s.as_str()), args.get(3)) {
+                    (None | Some("show"), _) => get_session_state().map(|state| {
+                        println!("{}", serde_json::to_string_pretty(&state).unwrap());
+                    }),
+                    (Some("set"), Some(spec)) => set_session_jdk(spec).map(|home| {
+                        println!("Session JDK set to: {}", home);
+                    }),
+                    (Some("clear"), _) => clear_session_jdk().map(|_| {
+                        println!("Session JDK cleared; following the global selection");
+                    }),
+                    _ => {
+                        eprintln!("Usage:");
+                        eprintln!("  {} session [show]", args[0]);
+                        eprintln!("  {} session set <id|home|version>", args[0]);
+                        eprintln!("  {} session clear", args[0]);
+                        std::process::exit(1);
+                    }
+                };
+                if let Err(e) = result {
+                    eprintln!("Error: {e}");
+                    std::process::exit(1);
+                }
+            }
+            "exec" => {
+                // exec <spec> [--] <command> [args...]
+                let spec = args.get(2);
+                let command_start = if args.get(3).map(|s| s.as_str()) == Some("--") { 4 } else { 3 };
+                let (spec, program) = match (spec, args.get(command_start)) {
+                    (Some(spec), Some(program)) => (spec, program),
+                    _ => {
+                        eprintln!("Usage: {} exec <profile|id|home|version> -- <command> [args...]", args[0]);
+                        std::process::exit(1);
+                    }
+                };
+                let environment = match jdk_environment(Some(spec), std::path::Path::new(".")) {
+                    Ok(environment) => environment,
+                    Err(e) => {
+                        eprintln!("Error: {e}");
+                        std::process::exit(1);
+                    }
+                };
+                let mut command = std::process::Command::new(program);
+                command.args(&args[command_start + 1..]).envs(environment.variables());
+                run_command(command, program);
+            }
+            "env" => {
+                let mut format = EnvFormat::Posix;
+                let mut spec = None;
+                let mut rest = args.iter().skip(2);
+                while let Some(arg) = rest.next() {
+                    match arg.as_str() {
+                        "--format" | "-f" => {
+                            let name = rest.next().map(|s| s.as_str()).unwrap_or("");
+                            format = match EnvFormat::from_name(name) {
+                                Some(format) => format,
+                                None => {
+                                    let known: Vec<&str> = EnvFormat::ALL.iter().map(|f| f.name()).collect();
+                                    eprintln!("Unknown format: {} (known: {})", name, known.join(", "));
+                                    std::process::exit(1);
+                                }
+                            };
+                        }
+                        _ if spec.is_none() => spec = Some(arg.as_str()),
+                        _ => {
+                            eprintln!("Usage: {} env [--format <format>] [profile|id|home|version]", args[0]);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                let dir = std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."));
+                match jdk_environment(spec, &dir) {
+                    Ok(environment) => {
+                        print!("{}", render_environment(&environment, format));
+                    }
+                    Err(e) => {
+                        eprintln!("Error: {e}");
+                        std::process::exit(1);
+                    }
+                }
+            }
+            "config" => {
+                match args.get(2).map(|s| s.as_str()) {
+                    Some("path") => {
+                        println!("{}", get_config_file_path().display());
+                    }
+                    None | Some("show") | Some("check") => {
+                        let config = match load_config() {
+                            Ok(config) => config,
+                            Err(e) => {
+                                eprintln!("Error: {e}");
+                                std::process::exit(1);
+                            }
+                        };
+                        if args.get(2).map(|s| s.as_str()) == Some("check") {
+                            let problems = validate_config(&config);
+                            if problems.is_empty() {
+                                println!("{}: OK", get_config_file_path().display());
+                            } else {
+                                for problem in &problems {
+                                    eprintln!("{}", problem);
+                                }
+                                std::process::exit(1);
+                            }
+                        } else {
+                            println!("{}", serde_json::to_string_pretty(&config).unwrap());
+                        }
+                    }
+                    Some(other) => {
+                        eprintln!("Unknown config command: {}", other);
+                        eprintln!("Usage: {} config [show|check|path]", args[0]);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            "shell" => {
+                let mut shells = Vec::new();
+                for name in args.iter().skip(3) {
+                    match Shell::from_name(name) {
+                        Some(shell) => shells.push(shell),
+                        None => {
+                            eprintln!("Unknown shell: {}", name);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                let cli = match std::env::current_exe() {
+                    Ok(cli) => cli,
+                    Err(e) => {
+                        eprintln!("Error: Could not locate the jdk-pulse executable: {e}");
+                        std::process::exit(1);
+                    }
+                };
+                let result = match args.get(2).map(|s| s.as_str()) {
+                    Some("install") if shells.is_empty() => install_shell_integration(&cli),
+                    Some("install") => install_shells(&shells, &cli),
+                    Some("remove") if shells.is_empty() => remove_shell_integration(),
+                    Some("remove") => remove_shells(&shells),
+                    Some("print") if shells.len() == 1 => {
+                        print!("{}", generate_hook(shells[0], &cli));
+                        return;
+                    }
+                    _ => {
+                        eprintln!("Usage:");
+                        eprintln!("  {} shell install [shell...]   Install hooks (default: shells from config)", args[0]);
+                        eprintln!("  {} shell remove [shell...]    Remove the managed hook blocks", args[0]);
+                        eprintln!("  {} shell print <shell>        Print the hook code", args[0]);
+                        std::process::exit(1);
+                    }
+                };
+                match result {
+                    Ok(changes) => {
+                        println!("{}", serde_json::to_string_pretty(&changes).unwrap());
+                        if changes.iter().any(|c| c.action == IntegrationAction::Conflict) {
+                            eprintln!("Some hook blocks were edited by hand or lack their end marker and were left alone; run `upgrade --force` to replace edited blocks");
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("Error: {e}");
+                        std::process::exit(1);
+                    }
+                }
+            }
+            "upgrade" => {
+                let force = args.iter().skip(2).any(|arg| arg == "--force");
+                let cli = match std::env::current_exe() {
+                    Ok(cli) => cli,
+                    Err(e) => {
+                        eprintln!("Error: Could not locate the jdk-pulse executable: {e}");
+                        std::process::exit(1);
+                    }
+                };
+                match upgrade_integrations(force, &cli) {
+                    Ok(changes) => {
+                        println!("{}", serde_json::to_string_pretty(&changes).unwrap());
+                        if changes.iter().any(|c| c.action == IntegrationAction::Conflict) {
+                            eprintln!("Some blocks were edited by hand or lack their end marker and were left alone; rerun with --force to replace edited blocks");
+                            std::process::exit(1);
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("Error: {e}");
+                        std::process::exit(1);
+                    }
+                }
+            }
+            "ps" => match list_jvms() {
+                Ok(jvms) => {
+                    println!("{}", serde_json::to_string_pretty(&jvms).unwrap());
+                }
+                Err(e) => {
+                    eprintln!("Error: {e}");
+                    std::process::exit(1);
+                }
+            },
+            "daemons" => {
+                let result = match args.get(2).map(|s| s.as_str()) {
+                    None => list_daemons().map(|daemons| serde_json::to_string_pretty(&daemons).unwrap()),
+                    Some("stop") => stop_stale_daemons().map(|stopped| serde_json::to_string_pretty(&stopped).unwrap()),
+                    _ => {
+                        eprintln!("Usage:");
+                        eprintln!("  {} daemons        List Gradle, Kotlin, mvnd and jdtls daemons and their JDK", args[0]);
+                        eprintln!("  {} daemons stop   Stop the daemons on another JDK than their session, project or global one", args[0]);
+                        std::process::exit(1);
+                    }
+                };
+                match result {
+                    Ok(json) => println!("{}", json),
+                    Err(e) => {
+                        eprintln!("Error: {e}");
+                        std::process::exit(1);
+                    }
+                }
+            }
+            "doctor" => match run_doctor_sync() {
+                Ok(report) => {
+                    println!("{}", serde_json::to_string_pretty(&report).unwrap());
+                    if report.status() == CheckStatus::Fail {
+                        std::process::exit(1);
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Error: {e}");
+                    std::process::exit(1);
+                }
+            },
+            "desktop" => {
+                let result = match args.get(2).map(|s| s.as_str()) {
+                    Some("sync") => match get_active_jdk() {
+                        Ok(Some(jdk)) => sync_desktop_environment(&jdk.home, &active_profile_env(), &[]).map(|_| {
+                            println!("Desktop environment set to: {}", jdk.home);
+                        }),
+                        Ok(None) => Err("No active JDK".to_string()),
+                        Err(e) => Err(e),
+                    },
+                    Some("remove") => remove_desktop_integration().map(|_| {
+                        println!("Desktop environment integration removed");
+                    }),
+                    _ => {
+                        eprintln!("Usage:");
+                        eprintln!("  {} desktop sync     Publish the active JDK to environment.d and the user manager", args[0]);
+                        eprintln!("  {} desktop remove   Remove it again", args[0]);
+                        std::process::exit(1);
+                    }
+                };
+                if let Err(e) = result {
+                    eprintln!("Error: {e}");
+                    std::process::exit(1);
+                }
+            }
+            "direnv" => {
+                let cli = match std::env::current_exe() {
+                    Ok(cli) => cli,
+                    Err(e) => {
+                        eprintln!("Error: Could not locate the jdk-pulse executable: {e}");
+                        std::process::exit(1);
+                    }
+                };
+                let result = match args.get(2).map(|s| s.as_str()) {
+                    Some("install") => install_direnv_integration(&cli),
+                    Some("remove") => remove_direnv_integration(),
+                    Some("print") => {
+                        println!("{}", generate_direnv_lib(&cli));
+                        return;
+                    }
+                    _ => {
+                        eprintln!("Usage: {} direnv install|remove|print", args[0]);
+                        std::process::exit(1);
+                    }
+                };
+                match result {
+                    Ok(change) => {
+                        println!("{}", serde_json::to_string_pretty(&change).unwrap());
+                    }
+                    Err(e) => {
+                        eprintln!("Error: {e}");
+                        std::process::exit(1);
+                    }
+                }
+            }
+            "completions" => {
+                let script = match args.get(2).and_then(|name| Shell::from_name(name)) {
+                    Some(shell) => generate_completions(shell),
+                    None => {
+                        eprintln!("Usage: {} completions bash|zsh|fish", args[0]);
+                        std::process::exit(1);
+                    }
+                };
+                match script {
+                    Ok(script) => print!("{}", script),
+                    Err(e) => {
+                        eprintln!("Error: {e}");
+                        std::process::exit(1);
+                    }
+                }
+            }
+            // Dynamic candidates for the completion scripts, one per line
+            "__complete" => {
+                match completion_candidates(args.get(2).map(|s| s.as_str()).unwrap_or("")) {
+                    Ok(candidates) => {
+                        for candidate in candidates {
+                            println!("{}", candidate);
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("Error: {e}");
+                        std::process::exit(1);
+                    }
+                }
+            }
+            "multiplexers" => {
+                if args.get(2).map(|s| s.as_str()) != Some("sync") {
+                    eprintln!("Usage:");
+                    eprintln!("  {} multiplexers sync   Set the active JDK in running tmux servers and screen sessions", args[0]);
+                    std::process::exit(1);
+                }
+                let result = match get_active_jdk() {
+                    Ok(Some(jdk)) => sync_multiplexers(&jdk.home, &active_profile_env(), &[]),
+                    Ok(None) => Err("No active JDK".to_string()),
+                    Err(e) => Err(e),
+                };
+                match result {
+                    Ok(servers) => {
+                        println!("{}", serde_json::to_string_pretty(&servers).unwrap());
+                    }
+                    Err(e) => {
+                        eprintln!("Error: {e}");
+                        std::process::exit(1);
+                    }
+                }
+            }
+            "shims" => {
+                let result = match args.get(2).map(|s| s.as_str()) {
+                    Some("install") => std::env::current_exe()
+                        .map_err(|e| format!("Could not locate the jdk-pulse executable: {e}"))
+                        .and_then(|cli| install_shims(&cli)),
+                    Some("remove") => remove_shims(),
+                    Some("path") => {
+                        println!("{}", get_shims_dir_path().display());
+                        return;
+                    }
+                    _ => {
+                        eprintln!("Usage:");
+                        eprintln!("  {} shims install   Write java, javac, ... shims (then add `shims path` to PATH)", args[0]);
+                        eprintln!("  {} shims remove    Delete the shims", args[0]);
+                        eprintln!("  {} shims path      Print the shims directory", args[0]);
+                        std::process::exit(1);
+                    }
+                };
+                match result {
+                    Ok(files) => {
+                        println!("{}", serde_json::to_string_pretty(&files).unwrap());
+                    }
+                    Err(e) => {
+                        eprintln!("Error: {e}");
+                        std::process::exit(1);
+                    }
+                }
+            }
             _ => {
                 eprintln!("Unknown command: {}", args[1]);
                 eprintln!("Usage:");
                 eprintln!("  {} [--list]     List all installed JDKs", args[0]);
-                eprintln!("  {} --set <id>   Set active JDK by ID or home path", args[0]);
+                eprintln!("  {} --set|use <id>  Set active JDK by ID, home path or version (e.g. 17)", args[0]);
                 eprintln!("  {} --get         Get current active JDK", args[0]);
+                eprintln!("  {} back          Undo the last switch (repeat to go further back)", args[0]);
+                eprintln!("  {} forward       Redo a switch undone with back", args[0]);
+                eprintln!("  {} history       Show recent JDK switches", args[0]);
+                eprintln!("  {} ps            List running JVMs and the JDK each one uses", args[0]);
+                eprintln!("  {} daemons [stop]  List build daemons, or stop those on an old JDK", args[0]);
+                eprintln!("  {} doctor        Check that a new login shell gets the selected JDK", args[0]);
+                eprintln!("  {} profile ...   List, add, remove or activate profiles", args[0]);
+                eprintln!("  {} session ...   Pin a JDK for the current shell only", args[0]);
+                eprintln!("  {} shell ...     Install, remove or print shell hooks", args[0]);
+                eprintln!("  {} upgrade [--force]  Rewrite outdated hook blocks (--force: also hand-edited ones)", args[0]);
+                eprintln!("  {} desktop ...   Sync or remove the desktop (environment.d) environment", args[0]);
+                eprintln!("  {} multiplexers sync  Set the active JDK in running tmux servers and screen sessions", args[0]);
+                eprintln!("  {} direnv ...    Install, remove or print the `use jdkpulse` direnv library", args[0]);
+                eprintln!("  {} completions <shell>  Print bash, zsh or fish completions", args[0]);
+                eprintln!("  {} shims ...     Install or remove java/javac/... shims", args[0]);
+                eprintln!("  {} config ...    Show, check or locate the config file", args[0]);
+                eprintln!("  {} project [dir] Show the JDK pinned by the nearest project file", args[0]);
+                eprintln!("  {} home [dir]    Print the effective JAVA_HOME for a directory", args[0]);
+                eprintln!("  {} exec <spec> -- <cmd>  Run one command with the given JDK", args[0]);
+                let formats: Vec<&str> = EnvFormat::ALL.iter().map(|f| f.name()).collect();
+                eprintln!("  {} env [spec]    Print activation code (--format {})", args[0], formats.join("|"));
                 std::process::exit(1);
             }
         }
@@ -69,3 +607,24 @@ fn main() {
     }
 }
 
+/// Replace this process with `command`, so the child receives signals directly
+/// and its exit status becomes ours.
+#[cfg(unix)]
+fn run_command(mut command: std::process::Command, program: &str) -> ! {
+    use std::os::unix::process::CommandExt;
+    let e = command.exec();
+    eprintln!("Error: failed to run {}: {e}", program);
+    std::process::exit(127);
+}
+
+/// Run `command` to completion and exit with its status code.
+#[cfg(not(unix))]
+fn run_command(mut command: std::process::Command, program: &str) -> ! {
+    match command.status() {
+        Ok(status) => std::process::exit(status.code().unwrap_or(1)),
+        Err(e) => {
+            eprintln!("Error: failed to run {}: {e}", program);
+            std::process::exit(127);
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::activation::{is_jdk_root, rewrite_path};
+use super::cached_jdks;
+use super::processes::{current_uid, current_user};
+
+/// Push `java_home` and the profile variables `env` into the global
+/// environment of every running tmux server and GNU screen session of the
+/// current user, so panes and windows opened afterwards start on them.
+/// `unset` names variables of the previous profile to remove. Enabled by
+/// `integrations.multiplexers`.
+///
+/// Returns the servers that were updated (`tmux <socket>`, `screen <session>`).
+/// Stale sockets are skipped.
+pub fn sync_multiplexers(
+    java_home: &str,
+    env: &BTreeMap<String, String>,
+    unset: &[String],
+) -> Result<Vec<String>, String> {
+    let bin = Path::new(java_home).join("bin").to_string_lossy().to_string();
+    let mut homes: Vec<String> = cached_jdks()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|jdk| jdk.home)
+        .collect();
+    homes.push(java_home.to_string());
+
+    let mut updated = Vec::new();
+    for socket in tmux_sockets() {
+        let socket = socket.to_string_lossy().to_string();
+        let tmux = |args: &[&str]| Command::new("tmux").arg("-S").arg(&socket).args(args).output();
+
+        // `show-environment` fails for a socket whose server is gone
+        let output = match tmux(&["show-environment", "-g"]) {
+            Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout).to_string(),
+            _ => continue,
+        };
+        let path = tmux_path(&output, &bin, &homes);
+
+        let ok = |args: &[&str]| tmux(args).map(|output| output.status.success()).unwrap_or(false);
+        let mut success = ok(&["set-environment", "-g", "JAVA_HOME", java_home])
+            && ok(&["set-environment", "-g", "PATH", &path]);
+        for (key, value) in env {
+            success &= ok(&["set-environment", "-g", key, value]);
+        }
+        for key in unset {
+            success &= ok(&["set-environment", "-g", "-u", key]);
+        }
+        if success {
+            updated.push(format!("tmux {}", socket));
+        } else {
+            eprintln!("Warning: could not update the tmux server at {}", socket);
+        }
+    }
+
+    for session in screen_sessions() {
+        // screen cannot report its environment; start from the server's own
+        // (Linux) or ours, dropping the JDK bin directories either way
+        let environ = session
+            .split('.')
+            .next()
+            .and_then(|pid| fs::read(format!("/proc/{}/environ", pid)).ok())
+            .map(|bytes| String::from_utf8_lossy(&bytes).to_string());
+        let path = environ
+            .as_deref()
+            .and_then(|environ| env_value(environ.split('\0'), "PATH"))
+            .or_else(|| std::env::var("PATH").ok())
+            .unwrap_or_default();
+        let mut server_homes = homes.clone();
+        let server_home = environ.as_deref().and_then(|environ| env_value(environ.split('\0'), "JAVA_HOME"));
+        server_homes.extend(
+            server_home
+                .into_iter()
+                .chain(std::env::var("JAVA_HOME").ok())
+                .filter(|home| is_jdk_root(home)),
+        );
+        let path = rewrite_path(&split(&path), &bin, &server_homes).join(":");
+
+        let screen = |args: &[&str]| {
+            Command::new("screen")
+                .args(["-S", &session, "-X"])
+                .args(args)
+                .status()
+                .map(|status| status.success())
+                .unwrap_or(false)
+        };
+        let mut success = screen(&["setenv", "JAVA_HOME", java_home]) && screen(&["setenv", "PATH", &path]);
+        for (key, value) in env {
+            success &= screen(&["setenv", key, value]);
+        }
+        for key in unset {
+            success &= screen(&["unsetenv", key]);
+        }
+        if success {
+            updated.push(format!("screen {}", session));
+        }
+    }
+    Ok(updated)
+}
+
+/// New global PATH for a tmux server, from its `show-environment -g` output.
+/// Only the bin directories of `homes` and of the server's JAVA_HOME (if it is
+/// a JDK root) go.
+fn tmux_path(show_environment: &str, bin: &str, homes: &[String]) -> String {
+    let mut server_homes = homes.to_vec();
+    server_homes.extend(env_value(show_environment.lines(), "JAVA_HOME").filter(|home| is_jdk_root(home)));
+    let path = env_value(show_environment.lines(), "PATH").unwrap_or_default();
+    rewrite_path(&split(&path), bin, &server_homes).join(":")
+}
+
+/// Sockets in the user's tmux directory, `${TMUX_TMPDIR:-/tmp}/tmux-<uid>`.
+fn tmux_sockets() -> Vec<PathBuf> {
+    let Some(uid) = current_uid() else {
+        return Vec::new();
+    };
+    let tmp = std::env::var_os("TMUX_TMPDIR")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("/tmp"));
+    list_dir(&tmp.join(format!("tmux-{}", uid)))
+        .into_iter()
+        .map(|(path, _)| path)
+        .collect()
+}
+
+/// Session names (`<pid>.<name>`) in `$SCREENDIR` or the usual
+/// `S-<user>` directories.
+fn screen_sessions() -> Vec<String> {
+    let dirs = match std::env::var_os("SCREENDIR").filter(|dir| !dir.is_empty()) {
+        Some(dir) => vec![PathBuf::from(dir)],
+        None => {
+            let Some(user) = current_user() else {
+                return Vec::new();
+            };
+            ["/run/screen", "/var/run/screen", "/tmp/screens", "/tmp/uscreens"]
+                .iter()
+                .map(|dir| Path::new(dir).join(format!("S-{}", user)))
+                .collect()
+        }
+    };
+    let mut sessions: Vec<String> = dirs
+        .iter()
+        .flat_map(|dir| list_dir(dir))
+        .map(|(_, name)| name)
+        .filter(|name| name.split('.').next().is_some_and(|pid| pid.parse::<u32>().is_ok()))
+        .collect();
+    // /var/run is usually a link to /run
+    sessions.sort();
+    sessions.dedup();
+    sessions
+}
+
+fn list_dir(dir: &Path) -> Vec<(PathBuf, String)> {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| (entry.path(), entry.file_name().to_string_lossy().to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// `KEY=value` lookup; tmux marks removed variables as `-KEY`, which never matches.
+fn env_value<'a>(mut lines: impl Iterator<Item = &'a str>, key: &str) -> Option<String> {
+    lines
+        .find_map(|line| line.strip_prefix(key).and_then(|rest| rest.strip_prefix('=')))
+        .map(|value| value.to_string())
+}
+
+fn split(path: &str) -> Vec<String> {
+    path.split(':')
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| entry.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::tmux_path;
+
+    #[test]
+    fn replaces_known_jdk_bin_directories() {
+        let output = "JAVA_HOME=/opt/jdk-17\nPATH=/opt/jdk-17/bin:/usr/bin:/opt/jdk-17-tools/bin:/bin\n";
+        let homes = vec!["/opt/jdk-17".to_string()];
+        assert_eq!(
+            tmux_path(output, "/opt/jdk-21/bin", &homes),
+            "/opt/jdk-21/bin:/usr/bin:/opt/jdk-17-tools/bin:/bin"
+        );
+    }
+
+    #[test]
+    fn keeps_system_directories_when_the_servers_java_home_is_usr() {
+        let output = "JAVA_HOME=/usr\nPATH=/usr/local/bin:/usr/bin:/bin\n-JDK_PULSE_SESSION\n";
+        assert_eq!(
+            tmux_path(output, "/opt/jdk-21/bin", &[]),
+            "/opt/jdk-21/bin:/usr/local/bin:/usr/bin:/bin"
+        );
+    }
+}
@@ -0,0 +1,271 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::project::find_project_jdk;
+use super::session::{get_sessions_dir_path, SESSION_ENV_VAR, SESSION_PID_ENV_VAR};
+use super::{cached_jdks, get_state_file_path};
+
+/// Process names treated as interactive shells.
+const SHELLS: &[&str] = &[
+    "bash", "zsh", "fish", "sh", "dash", "ksh", "mksh", "tcsh", "csh", "nu", "elvish", "xonsh",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProcessKind {
+    /// A shell whose environment holds `JAVA_HOME`
+    Shell,
+    /// A JVM running from a known JDK
+    Jvm,
+}
+
+/// A process of the current user still using another JDK than it should.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StaleProcess {
+    pub pid: u32,
+    /// Process name (`/proc/<pid>/comm`)
+    pub name: String,
+    /// Command line, arguments separated by spaces
+    pub command: String,
+    pub kind: ProcessKind,
+    /// JDK the process uses: the JDK its executable lives in (JVMs) or its
+    /// `JAVA_HOME` (shells)
+    pub java_home: String,
+    /// JDK it should use: its session or project pin, else `~/.jdk_current`
+    pub expected_home: String,
+}
+
+/// A process of the current user, read from `/proc`.
+pub(crate) struct ProcessEntry {
+    pub pid: u32,
+    pub ppid: Option<u32>,
+    pub name: String,
+    pub command: String,
+    pub exe: Option<PathBuf>,
+    pub cwd: Option<PathBuf>,
+    environ: Vec<u8>,
+}
+
+impl ProcessEntry {
+    /// Variable from the environment the process was started with.
+    /// Shells do not update this when they `export`.
+    pub fn env(&self, key: &str) -> Option<String> {
+        self.environ
+            .split(|byte| *byte == 0)
+            .find_map(|entry| entry.strip_prefix(key.as_bytes())?.strip_prefix(b"="))
+            .map(|value| String::from_utf8_lossy(value).to_string())
+            .filter(|value| !value.is_empty())
+    }
+}
+
+/// Shells and JVMs of the current user whose JDK differs from the one that
+/// applies to them (Linux only; elsewhere the list is empty).
+///
+/// A JVM's JDK is the known JDK home containing `/proc/<pid>/exe`, so bundled
+/// runtimes such as an IDE's are ignored. A shell's JDK is the `JAVA_HOME` it
+/// was started with; a shell with the jdk-pulse hook corrects its own
+/// environment at its next prompt, but `/proc` only shows the original one.
+pub fn find_stale_processes() -> Result<Vec<StaleProcess>, String> {
+    let active = fs::read_to_string(get_state_file_path()).unwrap_or_default();
+    let active = active.trim();
+    if active.is_empty() {
+        return Ok(Vec::new());
+    }
+    let homes: Vec<(String, PathBuf)> = cached_jdks()?
+        .into_iter()
+        .map(|jdk| jdk.home)
+        .chain(std::iter::once(active.to_string()))
+        .filter_map(|home| fs::canonicalize(&home).ok().map(|resolved| (home, resolved)))
+        .collect();
+
+    let mut stale = Vec::new();
+    for process in user_processes() {
+        let jvm_home = process.exe.as_ref().and_then(|exe| {
+            homes
+                .iter()
+                .find(|(_, home)| exe.starts_with(home))
+                .map(|(home, _)| home.clone())
+        });
+        let (kind, java_home) = match jvm_home {
+            Some(home) => (ProcessKind::Jvm, home),
+            None if SHELLS.contains(&process.name.as_str()) => match process.env("JAVA_HOME") {
+                Some(home) => (ProcessKind::Shell, home),
+                None => continue,
+            },
+            None => continue,
+        };
+        if same_jdk(&java_home, active) {
+            continue;
+        }
+        let expected_home = expected_home(&process, kind, process.cwd.as_deref()).unwrap_or_else(|| active.to_string());
+        if same_jdk(&java_home, &expected_home) {
+            continue;
+        }
+        stale.push(StaleProcess {
+            pid: process.pid,
+            name: process.name,
+            command: process.command,
+            kind,
+            java_home,
+            expected_home,
+        });
+    }
+    Ok(stale)
+}
+
+/// Session override (`JDK_PULSE_SESSION`, or the session file named after the
+/// pid), then the project pin of `dir` (usually its working directory).
+///
+/// A shell's hook replaces an inherited `JDK_PULSE_SESSION` unless the shell
+/// was started by the session's owner, so only then is the inherited one used.
+pub(crate) fn expected_home(process: &ProcessEntry, kind: ProcessKind, dir: Option<&Path>) -> Option<String> {
+    let inherited = kind == ProcessKind::Jvm
+        || process.ppid.is_some_and(|ppid| process.env(SESSION_PID_ENV_VAR) == Some(ppid.to_string()));
+    let session_file = process
+        .env(SESSION_ENV_VAR)
+        .filter(|_| inherited)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| get_sessions_dir_path().join(process.pid.to_string()));
+    let session = fs::read_to_string(session_file)
+        .map(|home| home.trim().to_string())
+        .unwrap_or_default();
+    if !session.is_empty() {
+        return Some(session);
+    }
+    find_project_jdk(dir?)
+        .ok()
+        .flatten()
+        .and_then(|project| project.jdk)
+        .map(|jdk| jdk.home)
+}
+
+#[cfg(test)]
+impl ProcessEntry {
+    /// A process with the given environment and no other details.
+    pub(crate) fn with_env(pid: u32, env: &[(&str, &str)]) -> ProcessEntry {
+        ProcessEntry {
+            pid,
+            ppid: None,
+            name: String::new(),
+            command: String::new(),
+            exe: None,
+            cwd: None,
+            environ: env
+                .iter()
+                .flat_map(|(key, value)| format!("{}={}\0", key, value).into_bytes())
+                .collect(),
+        }
+    }
+}
+
+/// Processes owned by the current user, except this one.
+pub(crate) fn user_processes() -> Vec<ProcessEntry> {
+    let Some(uid) = current_uid() else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    let own_pid = std::process::id();
+
+    let mut processes = Vec::new();
+    for entry in entries.flatten() {
+        let Some(pid) = entry.file_name().to_str().and_then(|name| name.parse::<u32>().ok()) else {
+            continue;
+        };
+        let dir = entry.path();
+        if pid == own_pid || file_owner(&dir) != Some(uid) {
+            continue;
+        }
+        // Processes may exit while being read; the fields are simply missing then
+        let command = read_command(&dir).unwrap_or_default();
+        processes.push(ProcessEntry {
+            pid,
+            ppid: parent_pid(&dir),
+            name: fs::read_to_string(dir.join("comm"))
+                .map(|name| name.trim().to_string())
+                .unwrap_or_default(),
+            command,
+            exe: fs::read_link(dir.join("exe")).ok(),
+            cwd: fs::read_link(dir.join("cwd")).ok(),
+            environ: fs::read(dir.join("environ")).unwrap_or_default(),
+        });
+    }
+    processes.sort_by_key(|process| process.pid);
+    processes
+}
+
+/// Command line of `pid` with the arguments joined by spaces, from `/proc`
+/// where there is one and `ps` elsewhere. None once the process is gone.
+pub(crate) fn command_line(pid: u32) -> Option<String> {
+    if Path::new("/proc").is_dir() {
+        return read_command(&Path::new("/proc").join(pid.to_string()));
+    }
+    let output = Command::new("ps")
+        .args(["-o", "command=", "-p", &pid.to_string()])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// `/proc/<pid>/cmdline` with the NUL-separated arguments joined by spaces.
+fn read_command(dir: &Path) -> Option<String> {
+    let bytes = fs::read(dir.join("cmdline")).ok()?;
+    Some(
+        bytes
+            .split(|byte| *byte == 0)
+            .filter(|arg| !arg.is_empty())
+            .map(|arg| String::from_utf8_lossy(arg).to_string())
+            .collect::<Vec<_>>()
+            .join(" "),
+    )
+}
+
+/// Parent pid from `/proc/<pid>/stat` (the field after the state; the
+/// command name before it may contain spaces and parentheses).
+fn parent_pid(dir: &Path) -> Option<u32> {
+    let stat = fs::read_to_string(dir.join("stat")).ok()?;
+    let (_, fields) = stat.rsplit_once(')')?;
+    fields.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// Equal after resolving symlinks.
+pub(crate) fn same_jdk(a: &str, b: &str) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => Path::new(a) == Path::new(b),
+    }
+}
+
+/// Login name of the calling user (`$USER`, else the home directory's name).
+pub(crate) fn current_user() -> Option<String> {
+    std::env::var("USER")
+        .ok()
+        .filter(|user| !user.is_empty())
+        .or_else(|| dirs::home_dir()?.file_name().map(|name| name.to_string_lossy().to_string()))
+}
+
+/// uid of the calling user: the owner of `/proc/self`, else of the home directory.
+#[cfg(unix)]
+pub(crate) fn current_uid() -> Option<u32> {
+    file_owner(Path::new("/proc/self")).or_else(|| file_owner(&dirs::home_dir()?))
+}
+
+#[cfg(not(unix))]
+pub(crate) fn current_uid() -> Option<u32> {
+    None
+}
+
+#[cfg(unix)]
+fn file_owner(path: &Path) -> Option<u32> {
+    use std::os::unix::fs::MetadataExt;
+    fs::metadata(path).ok().map(|metadata| metadata.uid())
+}
+
+#[cfg(not(unix))]
+fn file_owner(_path: &Path) -> Option<u32> {
+    None
+}
@@ -0,0 +1,186 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use super::{get_profile_env_file_path, switch_jdk};
+
+/// Header line identifying the active profile in the profile environment file
+const PROFILE_ENV_HEADER: &str = "# jdk-pulse profile: ";
+
+/// Variables managed by the shell hooks themselves; profiles may not override them
+const RESERVED_VARIABLES: &[&str] = &["JAVA_HOME", "PATH"];
+
+/// A named combination of a JDK spec and extra environment variables,
+/// e.g. "legacy" = Java 8 + `JAVA_TOOL_OPTIONS=-Dfile.encoding=UTF-8`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    /// JDK spec resolved through `set_active_jdk` (ID, home path or version such as `21`)
+    pub jdk: String,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+}
+
+/// On-disk representation: profiles keyed by name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredProfile {
+    jdk: String,
+    #[serde(default)]
+    env: BTreeMap<String, String>,
+}
+
+pub fn list_profiles() -> Result<Vec<Profile>, String> {
+    Ok(load_profiles()?
+        .into_iter()
+        .map(|(name, stored)| Profile {
+            name,
+            jdk: stored.jdk,
+            env: stored.env,
+        })
+        .collect())
+}
+
+pub fn get_profile(name: &str) -> Result<Profile, String> {
+    list_profiles()?
+        .into_iter()
+        .find(|p| p.name == name)
+        .ok_or_else(|| format!("Profile '{}' not found", name))
+}
+
+/// Create or replace a profile.
+pub fn save_profile(profile: &Profile) -> Result<(), String> {
+    validate_profile(profile)?;
+
+    let mut profiles = load_profiles()?;
+    profiles.insert(
+        profile.name.clone(),
+        StoredProfile {
+            jdk: profile.jdk.clone(),
+            env: profile.env.clone(),
+        },
+    );
+    store_profiles(&profiles)
+}
+
+pub fn remove_profile(name: &str) -> Result<(), String> {
+    let mut profiles = load_profiles()?;
+    if profiles.remove(name).is_none() {
+        return Err(format!("Profile '{}' not found", name));
+    }
+    store_profiles(&profiles)
+}
+
+/// Activate a profile: switch the JDK and publish its environment for the
+/// shell hooks and the enabled desktop and multiplexer integrations.
+pub fn activate_profile(name: &str) -> Result<String, String> {
+    let profile = get_profile(name)?;
+    switch_jdk(&profile.jdk, true, Some(&profile))
+}
+
+/// Publish a profile's environment for the shell hooks.
+pub(crate) fn write_profile_env(profile: &Profile) -> Result<(), String> {
+    let mut contents = format!("{}{}\n", PROFILE_ENV_HEADER, profile.name);
+    for (key, value) in &profile.env {
+        contents.push_str(&format!("{}={}\n", key, value));
+    }
+    fs::write(get_profile_env_file_path(), contents)
+        .map_err(|e| format!("Error writing profile environment file: {e}"))
+}
+
+/// Name of the active profile, if the current selection came from one.
+pub fn active_profile() -> Option<String> {
+    let contents = fs::read_to_string(get_profile_env_file_path()).ok()?;
+    contents
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix(PROFILE_ENV_HEADER))
+        .map(|name| name.trim().to_string())
+}
+
+/// Environment variables of the active profile, as published for the shell hooks.
+pub fn active_profile_env() -> BTreeMap<String, String> {
+    let contents = fs::read_to_string(get_profile_env_file_path()).unwrap_or_default();
+    contents
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+/// Forget the active profile environment (called when a JDK is selected directly).
+pub(crate) fn clear_profile_env() {
+    let path = get_profile_env_file_path();
+    if path.exists() {
+        if let Err(e) = fs::remove_file(&path) {
+            eprintln!("Warning: could not remove {}: {e}", path.display());
+        }
+    }
+}
+
+fn validate_profile(profile: &Profile) -> Result<(), String> {
+    if profile.name.is_empty()
+        || !profile
+            .name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+    {
+        return Err(format!(
+            "Invalid profile name '{}': use letters, digits, '-', '_' or '.'",
+            profile.name
+        ));
+    }
+    if profile.jdk.trim().is_empty() {
+        return Err(format!("Profile '{}' has no JDK spec", profile.name));
+    }
+
+    for (key, value) in &profile.env {
+        let valid_key = key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid_key {
+            return Err(format!("Invalid environment variable name '{}'", key));
+        }
+        if RESERVED_VARIABLES.contains(&key.as_str()) {
+            return Err(format!("{} is managed by jdk-pulse and cannot be set by a profile", key));
+        }
+        if value.contains('\n') || value.contains('\r') {
+            return Err(format!("Value of {} must be a single line", key));
+        }
+    }
+
+    Ok(())
+}
+
+fn load_profiles() -> Result<BTreeMap<String, StoredProfile>, String> {
+    let path = get_profiles_file_path();
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(_) => return Ok(BTreeMap::new()),
+    };
+    if contents.trim().is_empty() {
+        return Ok(BTreeMap::new());
+    }
+
+    serde_json::from_str(&contents)
+        .map_err(|e| format!("Failed to parse profiles file {}: {e}", path.display()))
+}
+
+fn store_profiles(profiles: &BTreeMap<String, StoredProfile>) -> Result<(), String> {
+    let path = get_profiles_file_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Error creating config directory: {e}"))?;
+    }
+    let json = serde_json::to_string_pretty(profiles)
+        .map_err(|e| format!("Error serializing profiles: {e}"))?;
+    fs::write(&path, json).map_err(|e| format!("Error writing profiles file: {e}"))
+}
+
+/// `profiles.json` in the app config directory, e.g. `~/.config/jdk-pulse/profiles.json`.
+fn get_profiles_file_path() -> PathBuf {
+    match dirs::config_dir() {
+        Some(dir) => dir.join("jdk-pulse").join("profiles.json"),
+        None => PathBuf::from("profiles.json"),
+    }
+}
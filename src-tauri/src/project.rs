@@ -0,0 +1,342 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{buildfile, config, get_state_file_path, list_jdks, read_jdk_cache, resolve_jdk_with, session, JdkInfo};
+
+/// Project files that can pin a JDK, in priority order within one directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProjectFileKind {
+    /// `.jdkpulse.json` – `{ "jdk": "21" }`
+    JdkPulse,
+    /// `.java-version` (jenv, jabba, ...) – a single spec line
+    JavaVersion,
+    /// `.sdkmanrc` – `java=17.0.9-tem`
+    Sdkmanrc,
+    /// `.tool-versions` (asdf) – `java temurin-17.0.9+9`
+    ToolVersions,
+    /// `mise.toml` / `.mise.toml` – `[tools] java = "21"`
+    Mise,
+    /// `pom.xml` – compiler release/target or `java.version`, including local parents
+    Maven,
+    /// `build.gradle` / `build.gradle.kts` – toolchain or source/target compatibility
+    Gradle,
+}
+
+const PROJECT_FILES: &[(&str, ProjectFileKind)] = &[
+    (".jdkpulse.json", ProjectFileKind::JdkPulse),
+    (".java-version", ProjectFileKind::JavaVersion),
+    (".sdkmanrc", ProjectFileKind::Sdkmanrc),
+    (".tool-versions", ProjectFileKind::ToolVersions),
+    ("mise.toml", ProjectFileKind::Mise),
+    (".mise.toml", ProjectFileKind::Mise),
+];
+
+/// Build files, consulted after the version files of the same directory.
+const BUILD_FILES: &[(&str, ProjectFileKind)] = &[
+    ("pom.xml", ProjectFileKind::Maven),
+    ("build.gradle.kts", ProjectFileKind::Gradle),
+    ("build.gradle", ProjectFileKind::Gradle),
+];
+
+/// Names of every file the project resolver looks at, version files first.
+pub(crate) fn project_file_names() -> impl Iterator<Item = &'static str> {
+    PROJECT_FILES.iter().chain(BUILD_FILES).map(|(name, _)| *name)
+}
+
+/// JDK requested by a project file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectJdk {
+    /// JDK spec as written in the file, e.g. `17` or `temurin-21.0.1`
+    pub spec: String,
+    /// File that decided the JDK
+    pub source: String,
+    pub kind: ProjectFileKind,
+    /// Installed JDK matching the spec (None if nothing installed matches)
+    pub jdk: Option<JdkInfo>,
+}
+
+/// Where the effective JDK for a directory came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum JdkOrigin {
+    Session,
+    Project,
+    Global,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EffectiveJdk {
+    pub home: String,
+    pub origin: JdkOrigin,
+    /// File that decided the JDK (session file, project file or `~/.jdk_current`)
+    pub source: String,
+}
+
+/// Walk up from `dir` and return the JDK pinned by the nearest project file.
+pub fn find_project_jdk(dir: &Path) -> Result<Option<ProjectJdk>, String> {
+    let (spec, source, kind) = match find_project_spec(dir) {
+        Some(found) => found,
+        None => return Ok(None),
+    };
+
+    // The discovery cache keeps this fast for shims and hooks. Without a usable
+    // cache, or on a miss (maybe a newly installed JDK), discovery runs once.
+    let config = config::load_config_or_default();
+    let cached = read_jdk_cache().and_then(|jdks| resolve_jdk_with(&spec, &jdks, &config).ok());
+    let jdk = match cached {
+        Some(jdk) => Some(jdk),
+        None => resolve_jdk_with(&spec, &list_jdks()?, &config).ok(),
+    };
+
+    Ok(Some(ProjectJdk {
+        spec,
+        source: source.to_string_lossy().to_string(),
+        kind,
+        jdk,
+    }))
+}
+
+/// JDK that applies in `dir`: the current shell's session override, then the
+/// nearest project file, else `~/.jdk_current`.
+///
+/// A project file naming a JDK that is not installed is an error rather than
+/// a silent fallback to the global selection.
+pub fn effective_jdk(dir: &Path) -> Result<Option<EffectiveJdk>, String> {
+    if let Some(home) = session::session_jdk_home() {
+        let source = session::get_session_file_path().unwrap_or_default();
+        return Ok(Some(EffectiveJdk {
+            home,
+            origin: JdkOrigin::Session,
+            source: source.to_string_lossy().to_string(),
+        }));
+    }
+
+    if let Some(project) = find_project_jdk(dir)? {
+        return match project.jdk {
+            Some(jdk) => Ok(Some(EffectiveJdk {
+                home: jdk.home,
+                origin: JdkOrigin::Project,
+                source: project.source,
+            })),
+            None => Err(format!(
+                "{} requires JDK '{}', but no installed JDK matches",
+                project.source, project.spec
+            )),
+        };
+    }
+
+    let state_file = get_state_file_path();
+    let home = fs::read_to_string(&state_file).unwrap_or_default();
+    let home = home.trim();
+    if home.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(EffectiveJdk {
+        home: home.to_string(),
+        origin: JdkOrigin::Global,
+        source: state_file.to_string_lossy().to_string(),
+    }))
+}
+
+/// Find the nearest project file pinning a JDK, without resolving the spec.
+///
+/// Directories are checked from `dir` upwards and the nearest file of either
+/// kind wins; within one directory version files beat build files. A global
+/// `~/.tool-versions` therefore never overrides a project's `pom.xml`.
+fn find_project_spec(dir: &Path) -> Option<(String, PathBuf, ProjectFileKind)> {
+    for ancestor in dir.ancestors() {
+        for (file_name, kind) in PROJECT_FILES {
+            let path = ancestor.join(file_name);
+            let contents = match fs::read_to_string(&path) {
+                Ok(contents) => contents,
+                Err(_) => continue,
+            };
+            if let Some(spec) = parse_project_file(*kind, &contents) {
+                return Some((spec, path, *kind));
+            }
+        }
+
+        for (file_name, kind) in BUILD_FILES {
+            let path = ancestor.join(file_name);
+            if !path.is_file() {
+                continue;
+            }
+            let version = match kind {
+                ProjectFileKind::Maven => buildfile::maven_java_version(&path),
+                _ => fs::read_to_string(&path)
+                    .ok()
+                    .and_then(|script| buildfile::gradle_java_version(&script)),
+            };
+            if let Some(version) = version {
+                return Some((version, path, *kind));
+            }
+        }
+    }
+
+    None
+}
+
+/// Extract the JDK spec from the contents of a project file.
+fn parse_project_file(kind: ProjectFileKind, contents: &str) -> Option<String> {
+    let spec = match kind {
+        ProjectFileKind::JdkPulse => {
+            let value: serde_json::Value = serde_json::from_str(contents).ok()?;
+            value.get("jdk")?.as_str()?.to_string()
+        }
+        ProjectFileKind::JavaVersion => meaningful_lines(contents).next()?.to_string(),
+        ProjectFileKind::Sdkmanrc => meaningful_lines(contents)
+            .filter_map(|line| line.split_once('='))
+            .find(|(key, _)| key.trim() == "java")
+            .map(|(_, value)| value.trim().to_string())?,
+        ProjectFileKind::ToolVersions => meaningful_lines(contents)
+            .map(|line| line.split_whitespace().collect::<Vec<_>>())
+            .find(|fields| fields.first() == Some(&"java"))?
+            .get(1)?
+            .to_string(),
+        ProjectFileKind::Mise => parse_mise_java(contents)?,
+        ProjectFileKind::Maven | ProjectFileKind::Gradle => return None,
+    };
+
+    let spec = spec.trim();
+    if spec.is_empty() {
+        None
+    } else {
+        Some(spec.to_string())
+    }
+}
+
+/// Read `java` from the `[tools]` table of a mise config. Handles the string
+/// (`java = "21"`), array (`java = ["21", "17"]`) and table
+/// (`java = { version = "21" }`) forms by taking the first quoted value.
+fn parse_mise_java(contents: &str) -> Option<String> {
+    let mut in_tools = false;
+    for line in meaningful_lines(contents) {
+        if line.starts_with('[') {
+            in_tools = line == "[tools]";
+            continue;
+        }
+        if !in_tools {
+            continue;
+        }
+        let (key, value) = match line.split_once('=') {
+            Some(pair) => pair,
+            None => continue,
+        };
+        if key.trim().trim_matches('"') != "java" {
+            continue;
+        }
+        let start = value.find(['"', '\''])?;
+        let quote = value[start..].chars().next()?;
+        let rest = &value[start + 1..];
+        return rest.find(quote).map(|end| rest[..end].to_string());
+    }
+    None
+}
+
+/// Trimmed lines that are neither empty nor `#` comments.
+fn meaningful_lines(contents: &str) -> impl Iterator<Item = &str> {
+    contents
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_project_spec, parse_mise_java, parse_project_file, ProjectFileKind};
+    use std::fs;
+
+    #[test]
+    fn project_file_specs() {
+        use ProjectFileKind::*;
+        let cases: &[(ProjectFileKind, &str, Option<&str>)] = &[
+            (JdkPulse, r#"{ "jdk": "21" }"#, Some("21")),
+            (JdkPulse, r#"{ "jdk": 21 }"#, None),
+            (JdkPulse, "not json", None),
+            (JavaVersion, "17\n", Some("17")),
+            (JavaVersion, "# pinned for CI\n\n  temurin-17.0.9  \n", Some("temurin-17.0.9")),
+            (JavaVersion, "1.8\n", Some("1.8")),
+            (JavaVersion, "\n# nothing\n", None),
+            (Sdkmanrc, "# sdk env\njava=17.0.9-tem\nmaven=3.9.5\n", Some("17.0.9-tem")),
+            (Sdkmanrc, "maven=3.9.5\n", None),
+            (Sdkmanrc, "java=\n", None),
+            (ToolVersions, "nodejs 20.10.0\njava temurin-17.0.9+9\npython 3.12.0\n", Some("temurin-17.0.9+9")),
+            (ToolVersions, "java corretto-21.0.1.12.1 temurin-21.0.1+12\n", Some("corretto-21.0.1.12.1")),
+            (ToolVersions, "# java 8\nnodejs 20.10.0\n", None),
+            (ToolVersions, "java\n", None),
+            (Mise, "[tools]\njava = \"21\"\n", Some("21")),
+            (Maven, "<project/>", None),
+            (Gradle, "java { toolchain { languageVersion = JavaLanguageVersion.of(17) } }", None),
+        ];
+        for (kind, contents, expected) in cases {
+            assert_eq!(
+                parse_project_file(*kind, contents).as_deref(),
+                *expected,
+                "{kind:?}: {contents:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn mise_java() {
+        let cases: &[(&str, Option<&str>)] = &[
+            ("[tools]\njava = \"temurin-21\"\n", Some("temurin-21")),
+            ("[tools]\nnode = \"20\"\njava = '17'\n", Some("17")),
+            ("[tools]\n\"java\" = \"21\"\n", Some("21")),
+            ("[tools]\njava = [\"21\", \"17\"]\n", Some("21")),
+            ("[tools]\njava = { version = \"corretto-17\" }\n", Some("corretto-17")),
+            ("[env]\nJAVA_OPTS = \"-Xmx1g\"\n\n[tools]\n# java = \"8\"\njava = \"11\"\n", Some("11")),
+            // java outside [tools] does not count
+            ("[env]\njava = \"21\"\n", None),
+            ("[tools]\nnode = \"20\"\n[settings]\njava = \"21\"\n", None),
+            ("", None),
+        ];
+        for (contents, expected) in cases {
+            assert_eq!(parse_mise_java(contents).as_deref(), *expected, "{contents:?}");
+        }
+    }
+
+    #[test]
+    fn finds_the_nearest_project_file() {
+        let root = std::env::temp_dir().join(format!("jdk-pulse-project-{}", std::process::id()));
+        let write = |relative: &str, contents: &str| {
+            let path = root.join(relative);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        };
+        write("app/.java-version", "17\n");
+        write("app/src/main/java/.keep", "");
+        write("app/.tool-versions", "java temurin-21.0.1+12\n");
+        write("mixed/pom.xml", "<project><properties><maven.compiler.release>11</maven.compiler.release></properties></project>");
+        write("mixed/.sdkmanrc", "java=21.0.1-tem\n");
+        write("nested/.tool-versions", "nodejs 20.10.0\njava corretto-8.392.08.1\n");
+        write("nested/service/build.gradle", "java { sourceCompatibility = JavaVersion.VERSION_17 }\n");
+        write("nested/tools/mise.toml", "[tools]\nnode = \"20\"\n");
+        write("nested/tools/deep/.keep", "");
+
+        // (directory, (spec, file, kind) found)
+        type Case<'a> = (&'a str, Option<(&'a str, &'a str, ProjectFileKind)>);
+        let cases: &[Case] = &[
+            // Walks up to the parent; .java-version beats .tool-versions
+            ("app/src/main/java", Some(("17", "app/.java-version", ProjectFileKind::JavaVersion))),
+            // Version files beat build files in the same directory
+            ("mixed", Some(("21.0.1-tem", "mixed/.sdkmanrc", ProjectFileKind::Sdkmanrc))),
+            // The nearest directory wins over a parent's version file
+            ("nested/service", Some(("17", "nested/service/build.gradle", ProjectFileKind::Gradle))),
+            // A mise.toml without java is skipped
+            ("nested/tools/deep", Some(("corretto-8.392.08.1", "nested/.tool-versions", ProjectFileKind::ToolVersions))),
+        ];
+        for (dir, expected) in cases {
+            let found = find_project_spec(&root.join(dir));
+            let expected = expected.map(|(spec, file, kind)| (spec.to_string(), root.join(file), kind));
+            assert_eq!(found, expected, "dir: {dir}");
+        }
+        // Nothing in the root itself; anything found lies above it
+        assert!(find_project_spec(&root).is_none_or(|(_, path, _)| !path.starts_with(&root)));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
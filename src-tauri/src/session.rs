@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use super::{get_state_dir_path, resolve_jdk};
+
+/// Environment variable pointing at the session state file of the current shell.
+/// The shell hooks set it to `<state dir>/sessions/<shell pid>` on startup.
+pub const SESSION_ENV_VAR: &str = "JDK_PULSE_SESSION";
+
+/// pid of the shell that last took over `JDK_PULSE_SESSION`. A shell keeps an
+/// inherited session only when this names itself (the hook was sourced again)
+/// or its parent (a nested shell); shells started by tmux, an IDE or a
+/// terminal emulator get a fresh session file instead.
+pub const SESSION_PID_ENV_VAR: &str = "JDK_PULSE_SESSION_PID";
+
+/// Session override of the current shell, if any.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionState {
+    /// Session state file named by `JDK_PULSE_SESSION`
+    pub file: String,
+    /// JAVA_HOME pinned for this session (None if the session follows the global state)
+    pub home: Option<String>,
+}
+
+/// Session state file of the current shell, from `JDK_PULSE_SESSION`.
+pub fn get_session_file_path() -> Option<PathBuf> {
+    std::env::var_os(SESSION_ENV_VAR)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Directory holding one state file per shell session.
+pub fn get_sessions_dir_path() -> PathBuf {
+    get_state_dir_path().join("sessions")
+}
+
+pub fn get_session_state() -> Result<SessionState, String> {
+    let file = require_session_file()?;
+    Ok(SessionState {
+        file: file.to_string_lossy().to_string(),
+        home: session_jdk_home(),
+    })
+}
+
+/// JAVA_HOME pinned for the current shell session, if any.
+pub fn session_jdk_home() -> Option<String> {
+    let contents = fs::read_to_string(get_session_file_path()?).ok()?;
+    let home = contents.trim();
+    if home.is_empty() {
+        None
+    } else {
+        Some(home.to_string())
+    }
+}
+
+/// Pin a JDK for the current shell only; `~/.jdk_current` is left untouched.
+pub fn set_session_jdk(spec: &str) -> Result<String, String> {
+    let file = require_session_file()?;
+    let jdk = resolve_jdk(spec)?;
+
+    if let Some(parent) = file.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Error creating session directory: {e}"))?;
+    }
+    fs::write(&file, &jdk.home).map_err(|e| format!("Error writing session file: {e}"))?;
+
+    prune_stale_sessions();
+    Ok(jdk.home)
+}
+
+/// Drop the session override so the shell follows the global selection again.
+pub fn clear_session_jdk() -> Result<(), String> {
+    let file = require_session_file()?;
+    if file.exists() {
+        fs::remove_file(&file).map_err(|e| format!("Error removing session file: {e}"))?;
+    }
+    Ok(())
+}
+
+fn require_session_file() -> Result<PathBuf, String> {
+    get_session_file_path().ok_or_else(|| {
+        format!(
+            "{} is not set; install the shell hook or export {}=<file> in this shell",
+            SESSION_ENV_VAR, SESSION_ENV_VAR
+        )
+    })
+}
+
+/// Remove session files (`<pid>`) and project stamps of the shell hooks
+/// (`<pid>.stamp`) left behind by shells that have exited.
+#[cfg(target_os = "linux")]
+fn prune_stale_sessions() {
+    let entries = match fs::read_dir(get_sessions_dir_path()) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let pid = name.to_str().map(|s| s.strip_suffix(".stamp").unwrap_or(s));
+        let pid = match pid.and_then(|s| s.parse::<u32>().ok()) {
+            Some(pid) => pid,
+            None => continue,
+        };
+        if !PathBuf::from("/proc").join(pid.to_string()).exists() {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn prune_stale_sessions() {}
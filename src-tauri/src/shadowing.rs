@@ -0,0 +1,178 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use super::cached_jdks;
+
+/// Tools whose PATH order the doctor reports.
+pub const PATH_TOOLS: &[&str] = &["java", "javac", "mvn", "gradle"];
+
+/// Longest symlink chain followed before giving up (loops).
+const MAX_LINKS: usize = 40;
+
+/// One executable found on PATH.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathHit {
+    pub path: String,
+    /// Symlinks followed from `path`, ending at the real executable
+    /// (e.g. `/etc/alternatives/java`, then `/usr/lib/jvm/.../bin/java`)
+    pub links: Vec<String>,
+    /// JDK the executable belongs to, if any
+    pub jdk_home: Option<String>,
+}
+
+/// Every executable named `tool` on PATH, in lookup order; the first one wins.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolOnPath {
+    pub tool: String,
+    pub hits: Vec<PathHit>,
+}
+
+/// Look up each of `tools` in every directory of `path` (a `:`-separated PATH).
+///
+/// Each hit is resolved through its symlink chain and matched against the
+/// known JDK homes plus `extra_homes`, or else recognised as a JDK by the
+/// `release` file next to its `bin` directory. `launcher_home` is what the
+/// macOS `/usr/bin/java` launcher stubs run (the shell's `JAVA_HOME`).
+pub fn scan_path(
+    path: &str,
+    tools: &[&str],
+    extra_homes: &[String],
+    launcher_home: Option<&str>,
+) -> Vec<ToolOnPath> {
+    let mut homes: Vec<String> = cached_jdks()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|jdk| jdk.home)
+        .collect();
+    homes.extend(extra_homes.iter().cloned());
+    let homes: Vec<(String, PathBuf)> = homes
+        .into_iter()
+        .filter_map(|home| fs::canonicalize(&home).ok().map(|resolved| (home, resolved)))
+        .collect();
+
+    let mut dirs: Vec<&str> = Vec::new();
+    for dir in path.split(':').filter(|dir| !dir.is_empty()) {
+        if !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
+    }
+
+    tools
+        .iter()
+        .map(|tool| ToolOnPath {
+            tool: tool.to_string(),
+            hits: dirs
+                .iter()
+                .map(|dir| Path::new(dir).join(tool))
+                .filter(|candidate| is_executable(candidate))
+                .map(|candidate| {
+                    let links = symlink_chain(&candidate);
+                    let jdk_home = if is_launcher_stub(&candidate, &links) {
+                        launcher_home.map(|home| home.to_string())
+                    } else {
+                        owning_jdk(&candidate, &homes)
+                    };
+                    PathHit {
+                        path: candidate.to_string_lossy().to_string(),
+                        links,
+                        jdk_home,
+                    }
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// `path -> link -> ... -> target [jdk home]`, for one hit.
+pub fn describe_hit(hit: &PathHit) -> String {
+    let mut text = hit.path.clone();
+    for link in &hit.links {
+        text.push_str(" → ");
+        text.push_str(link);
+    }
+    if let Some(home) = &hit.jdk_home {
+        text.push_str(&format!(" [{}]", home));
+    }
+    text
+}
+
+/// Targets of the symlinks starting at `path`, made absolute.
+fn symlink_chain(path: &Path) -> Vec<String> {
+    let mut links = Vec::new();
+    let mut current = path.to_path_buf();
+    while links.len() < MAX_LINKS {
+        let Ok(target) = fs::read_link(&current) else {
+            break;
+        };
+        current = if target.is_absolute() {
+            target
+        } else {
+            normalize(&current.parent().unwrap_or(Path::new("/")).join(target))
+        };
+        links.push(current.to_string_lossy().to_string());
+    }
+    links
+}
+
+/// Remove `.` and `..` without touching the filesystem.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// A known home containing the resolved executable, else the directory above
+/// its `bin` (or `jre/bin`) when that looks like a JDK.
+fn owning_jdk(executable: &Path, homes: &[(String, PathBuf)]) -> Option<String> {
+    let resolved = fs::canonicalize(executable).ok()?;
+    if let Some((home, _)) = homes.iter().find(|(_, home)| resolved.starts_with(home)) {
+        return Some(home.clone());
+    }
+    let bin = resolved.parent()?;
+    if bin.file_name()? != "bin" {
+        return None;
+    }
+    let mut home = bin.parent()?;
+    if home.file_name().is_some_and(|name| name == "jre") && !home.join("release").is_file() {
+        home = home.parent()?;
+    }
+    home.join("release")
+        .is_file()
+        .then(|| home.to_string_lossy().to_string())
+}
+
+/// `/usr/bin/java` and friends on macOS are launchers that run the JDK named by
+/// `JAVA_HOME` (or `java_home`), not symlinks.
+#[cfg(target_os = "macos")]
+fn is_launcher_stub(path: &Path, links: &[String]) -> bool {
+    links.is_empty()
+        && path.parent() == Some(Path::new("/usr/bin"))
+        && path.file_name().is_some_and(|name| name == "java" || name == "javac")
+}
+
+#[cfg(not(target_os = "macos"))]
+fn is_launcher_stub(_path: &Path, _links: &[String]) -> bool {
+    false
+}
+
+#[cfg(unix)]
+pub(crate) fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path)
+        .map(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+pub(crate) fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
@@ -0,0 +1,127 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::get_state_file_path;
+use super::hooks::{sh_escape, sh_path};
+use super::project::project_file_names;
+
+/// JDK tools that get a shim.
+pub const SHIM_TOOLS: &[&str] = &[
+    "java", "javac", "javadoc", "javap", "jar", "jarsigner", "jshell", "jcmd", "jconsole", "jdb",
+    "jdeps", "jfr", "jinfo", "jlink", "jmap", "jpackage", "jps", "jstack", "jstat", "jwebserver",
+    "keytool", "serialver",
+];
+
+/// Marker identifying files written by `install_shims`.
+const SHIM_MARKER: &str = "# jdk-pulse shim";
+
+/// `$XDG_DATA_HOME/jdk-pulse/shims`, default `~/.local/share/jdk-pulse/shims`.
+/// Put it on PATH (ahead of any system `java`) to enable shims mode.
+pub fn get_shims_dir_path() -> PathBuf {
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".local").join("share")))
+        .unwrap_or_else(|| PathBuf::from("."));
+    data_home.join("jdk-pulse").join("shims")
+}
+
+/// Write one shim per tool in `SHIM_TOOLS`, calling `cli` when a project file
+/// needs resolving. Returns the shim paths.
+pub fn install_shims(cli: &Path) -> Result<Vec<String>, String> {
+    let dir = get_shims_dir_path();
+    fs::create_dir_all(&dir).map_err(|e| format!("Error creating {}: {e}", dir.display()))?;
+
+    let mut written = Vec::new();
+    for tool in SHIM_TOOLS {
+        let path = dir.join(tool);
+        fs::write(&path, generate_shim(tool, cli))
+            .map_err(|e| format!("Error writing {}: {e}", path.display()))?;
+        make_executable(&path)?;
+        written.push(path.to_string_lossy().to_string());
+    }
+    Ok(written)
+}
+
+/// Delete every shim (and the shims directory once empty). Returns the removed paths.
+pub fn remove_shims() -> Result<Vec<String>, String> {
+    let dir = get_shims_dir_path();
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(_) => return Ok(Vec::new()),
+    };
+
+    let mut removed = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let is_shim = fs::read_to_string(&path)
+            .map(|contents| contents.contains(SHIM_MARKER))
+            .unwrap_or(false);
+        if is_shim {
+            fs::remove_file(&path).map_err(|e| format!("Error removing {}: {e}", path.display()))?;
+            removed.push(path.to_string_lossy().to_string());
+        }
+    }
+    let _ = fs::remove_dir(&dir);
+    Ok(removed)
+}
+
+/// POSIX sh shim for `tool`.
+///
+/// The common cases stay in the shell: a session override or, when no project
+/// file exists up the tree, `~/.jdk_current`. Only inside a project is the CLI
+/// asked (`home`), which resolves against the discovery cache.
+fn generate_shim(tool: &str, cli: &Path) -> String {
+    let state_file = sh_path(&get_state_file_path());
+    let cli = sh_escape(&cli.to_string_lossy());
+    let project_files = project_file_names().collect::<Vec<_>>().join(" ");
+
+    format!(
+        r#"#!/bin/sh
+{SHIM_MARKER}: resolves the effective JDK on every call
+tool={tool}
+home=
+if [ -n "$JDK_PULSE_SESSION" ] && [ -s "$JDK_PULSE_SESSION" ]; then
+  IFS= read -r home < "$JDK_PULSE_SESSION" || :
+else
+  dir=$PWD
+  while :; do
+    for file in {project_files}; do
+      if [ -f "$dir/$file" ]; then
+        home=$("{cli}" home) || exit 1
+        break 2
+      fi
+    done
+    [ -z "$dir" ] && break
+    dir=${{dir%/*}}
+  done
+  if [ -z "$home" ] && [ -s "{state_file}" ]; then
+    IFS= read -r home < "{state_file}" || :
+  fi
+fi
+if [ -z "$home" ]; then
+  echo "jdk-pulse: no JDK selected; run 'jdk-pulse --set <version>'" >&2
+  exit 1
+fi
+if [ ! -x "$home/bin/$tool" ]; then
+  echo "jdk-pulse: $tool is not available in $home" >&2
+  exit 127
+fi
+JAVA_HOME=$home
+export JAVA_HOME
+exec "$home/bin/$tool" "$@"
+"#
+    )
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+        .map_err(|e| format!("Error making {} executable: {e}", path.display()))
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> Result<(), String> {
+    Ok(())
+}
@@ -0,0 +1,272 @@
+use std::cmp::Ordering;
+
+use super::config::Config;
+use super::JdkInfo;
+
+/// Vendor keywords for the short distribution codes used by SDKMAN, asdf and mise.
+const VENDOR_ALIASES: &[(&str, &[&str])] = &[
+    ("tem", &["temurin", "adoptium"]),
+    ("temurin", &["temurin", "adoptium"]),
+    ("adoptopenjdk", &["adoptopenjdk", "adoptium", "temurin"]),
+    ("amzn", &["corretto", "amazon"]),
+    ("corretto", &["corretto", "amazon"]),
+    ("zulu", &["zulu", "azul"]),
+    ("librca", &["liberica", "bellsoft"]),
+    ("liberica", &["liberica", "bellsoft"]),
+    ("graal", &["graal"]),
+    ("graalce", &["graal"]),
+    ("oracle", &["oracle"]),
+    ("open", &["openjdk"]),
+    ("openjdk", &["openjdk"]),
+    ("ms", &["microsoft"]),
+    ("microsoft", &["microsoft"]),
+    ("sapmchn", &["sapmachine"]),
+    ("sapmachine", &["sapmachine"]),
+    ("sem", &["semeru", "ibm"]),
+    ("semeru", &["semeru", "ibm"]),
+];
+
+/// Find the installed JDK best matching `spec`.
+///
+/// Accepted forms:
+/// - a JDK ID or home path from `list_jdks`
+/// - a version: `17`, `1.8`, `21.0.1`, `17.0.2+8`
+/// - a vendor-qualified version: `temurin-17`, `17.0.9-tem` (SDKMAN), `corretto@21`
+///
+/// Config aliases are expected to be resolved already (see `resolve_jdk`).
+/// When several JDKs match, those whose vendor matches the spec are preferred,
+/// then the config's `preferred_vendors` in order, then the highest version.
+pub fn find_jdk_by_spec<'a>(
+    spec: &str,
+    jdks: &'a [JdkInfo],
+    config: &Config,
+) -> Option<&'a JdkInfo> {
+    let spec = spec.trim();
+    if spec.is_empty() {
+        return None;
+    }
+
+    if let Some(jdk) = jdks.iter().find(|j| j.id == spec || j.home == spec) {
+        return Some(jdk);
+    }
+
+    let (version, vendor_hints) = split_spec(spec);
+    let version = version?;
+
+    let candidates: Vec<&JdkInfo> = jdks
+        .iter()
+        .filter(|j| version_matches(&version, j))
+        .collect();
+    let preferred: Vec<&JdkInfo> = candidates
+        .iter()
+        .copied()
+        .filter(|j| vendor_matches(&vendor_hints, j))
+        .collect();
+
+    let pool = if preferred.is_empty() { candidates } else { preferred };
+    pool.into_iter().max_by(|a, b| {
+        preferred_vendor_rank(a, config)
+            .cmp(&preferred_vendor_rank(b, config))
+            .then_with(|| compare_versions(&a.version_full, &b.version_full))
+    })
+}
+
+/// Higher is better: the first preferred vendor ranks highest, unlisted vendors rank 0.
+fn preferred_vendor_rank(jdk: &JdkInfo, config: &Config) -> usize {
+    let preferred = &config.preferred_vendors;
+    preferred
+        .iter()
+        .position(|vendor| vendor_matches(std::slice::from_ref(&vendor.to_lowercase()), jdk))
+        .map(|index| preferred.len() - index)
+        .unwrap_or(0)
+}
+
+/// Split a spec such as `temurin-17.0.2` or `17.0.2-tem` into its version
+/// and lowercase vendor hints.
+fn split_spec(spec: &str) -> (Option<String>, Vec<String>) {
+    let mut version = None;
+    let mut vendor_hints = Vec::new();
+
+    for token in spec.split(['-', '@']).filter(|t| !t.is_empty()) {
+        if version.is_none() && token.starts_with(|c: char| c.is_ascii_digit()) {
+            version = Some(token.to_string());
+        } else {
+            vendor_hints.push(token.to_lowercase());
+        }
+    }
+
+    (version, vendor_hints)
+}
+
+fn version_matches(version: &str, jdk: &JdkInfo) -> bool {
+    let wanted = version_components(version);
+    if wanted.first() != Some(&u64::from(jdk.version_major)) {
+        return false;
+    }
+
+    // Compare the remaining components against whatever follows the major
+    // version in version_full (which may carry a prefix such as "openjdk64-")
+    let have = version_components(&jdk.version_full);
+    match have.iter().position(|&n| n == u64::from(jdk.version_major)) {
+        Some(start) => have[start + 1..].starts_with(&wanted[1..]),
+        None => wanted.len() == 1,
+    }
+}
+
+/// Numeric components of a version, with build metadata (`+8`) dropped and
+/// the legacy `1.` prefix removed, so `1.8.0_382` and `8.0.382` compare equal.
+fn version_components(version: &str) -> Vec<u64> {
+    let version = version.split('+').next().unwrap_or(version);
+    let mut parts = numeric_parts(version);
+    if version.starts_with("1.") && parts.len() > 1 {
+        parts.remove(0);
+    }
+    parts
+}
+
+fn numeric_parts(s: &str) -> Vec<u64> {
+    s.split(|c: char| !c.is_ascii_digit())
+        .filter(|part| !part.is_empty())
+        .filter_map(|part| part.parse().ok())
+        .collect()
+}
+
+fn vendor_matches(hints: &[String], jdk: &JdkInfo) -> bool {
+    if hints.is_empty() {
+        return false;
+    }
+
+    let haystack = format!(
+        "{} {} {}",
+        jdk.id,
+        jdk.vendor.as_deref().unwrap_or(""),
+        jdk.home
+    )
+    .to_lowercase();
+
+    hints.iter().any(|hint| {
+        let keywords = VENDOR_ALIASES
+            .iter()
+            .find(|(alias, _)| alias == hint)
+            .map(|(_, keywords)| keywords.to_vec())
+            .unwrap_or_else(|| vec![hint.as_str()]);
+        keywords.iter().any(|k| haystack.contains(k))
+    })
+}
+
+/// Compare two version strings numerically component by component (`1.8.0_382`, `21.0.1+12`).
+pub(crate) fn compare_versions(a: &str, b: &str) -> Ordering {
+    numeric_parts(a).cmp(&numeric_parts(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_jdk_by_spec, split_spec, version_matches};
+    use crate::config::Config;
+    use crate::JdkInfo;
+
+    fn jdk(id: &str, version_full: &str, vendor: Option<&str>) -> JdkInfo {
+        JdkInfo {
+            id: id.to_string(),
+            version_major: super::version_components(version_full)[0] as u32,
+            version_full: version_full.to_string(),
+            home: format!("/opt/jdks/{}", id),
+            vendor: vendor.map(str::to_string),
+        }
+    }
+
+    fn installed() -> Vec<JdkInfo> {
+        vec![
+            jdk("zulu-8", "1.8.0_382", Some("Azul Systems, Inc.")),
+            jdk("temurin-17", "17.0.9+9", Some("Eclipse Adoptium")),
+            jdk("corretto-17", "17.0.10+7", Some("Amazon.com Inc.")),
+            jdk("temurin-21", "21.0.1+12", Some("Eclipse Adoptium")),
+            jdk("openjdk-21", "21.0.2", None),
+        ]
+    }
+
+    #[test]
+    fn split_specs() {
+        let cases: &[(&str, Option<&str>, &[&str])] = &[
+            ("17", Some("17"), &[]),
+            ("temurin-17.0.2", Some("17.0.2"), &["temurin"]),
+            ("17.0.9-tem", Some("17.0.9"), &["tem"]),
+            ("corretto@21", Some("21"), &["corretto"]),
+            ("Zulu-8", Some("8"), &["zulu"]),
+            ("temurin", None, &["temurin"]),
+        ];
+        for (spec, version, hints) in cases {
+            let (got_version, got_hints) = split_spec(spec);
+            assert_eq!(got_version.as_deref(), *version, "spec: {spec}");
+            assert_eq!(got_hints, hints.to_vec(), "spec: {spec}");
+        }
+    }
+
+    #[test]
+    fn version_matching() {
+        // (spec version, version_major, version_full, matches)
+        let cases: &[(&str, u32, &str, bool)] = &[
+            ("8", 8, "1.8.0_382", true),
+            ("1.8", 8, "1.8.0_382", true),
+            ("1.8.0", 8, "1.8.0_382", true),
+            ("8.0.382", 8, "1.8.0_382", true),
+            ("1.7", 8, "1.8.0_382", false),
+            ("17", 17, "17.0.9+9", true),
+            ("17.0.9", 17, "17.0.9+9", true),
+            ("17.0.9+9", 17, "17.0.9+9", true),
+            ("17.0.10", 17, "17.0.9+9", false),
+            ("1", 17, "17.0.9+9", false),
+            // jenv versions carry a prefix before the major version
+            ("21", 21, "openjdk64-21.0.1", true),
+            ("21.0.1", 21, "openjdk64-21.0.1", true),
+            ("21.0.2", 21, "openjdk64-21.0.1", false),
+        ];
+        for (version, major, version_full, expected) in cases {
+            let jdk = JdkInfo {
+                version_major: *major,
+                ..jdk("jdk", version_full, None)
+            };
+            assert_eq!(version_matches(version, &jdk), *expected, "{version} vs {version_full}");
+        }
+    }
+
+    #[test]
+    fn find_by_spec() {
+        let jdks = installed();
+        let cases: &[(&str, &[&str], Option<&str>)] = &[
+            ("temurin-17", &[], Some("temurin-17")),
+            ("/opt/jdks/openjdk-21", &[], Some("openjdk-21")),
+            // Highest version without a vendor hint or preference
+            ("17", &[], Some("corretto-17")),
+            ("21", &[], Some("openjdk-21")),
+            ("8", &[], Some("zulu-8")),
+            ("1.8", &[], Some("zulu-8")),
+            // Vendor prefixes and SDKMAN/asdf suffixes
+            ("temurin-17.0", &[], Some("temurin-17")),
+            ("17.0.9-tem", &[], Some("temurin-17")),
+            ("amzn-17", &[], Some("corretto-17")),
+            ("adoptium@21", &[], Some("temurin-21")),
+            // An unknown vendor falls back to any JDK of that version
+            ("liberica-21", &[], Some("openjdk-21")),
+            // preferred_vendors decides between equally matching JDKs
+            ("17", &["temurin"], Some("temurin-17")),
+            ("21", &["corretto", "temurin"], Some("temurin-21")),
+            ("corretto-17", &["temurin"], Some("corretto-17")),
+            ("11", &[], None),
+            ("17.0.11", &[], None),
+            ("temurin", &[], None),
+            ("  ", &[], None),
+        ];
+        for (spec, preferred, expected) in cases {
+            let config = Config {
+                preferred_vendors: preferred.iter().map(|v| v.to_string()).collect(),
+                ..Config::default()
+            };
+            assert_eq!(
+                find_jdk_by_spec(spec, &jdks, &config).map(|j| j.id.as_str()),
+                *expected,
+                "spec: {spec:?}, preferred: {preferred:?}"
+            );
+        }
+    }
+}
@@ -3,6 +3,9 @@
 #[cfg(feature = "tauri")]
 fn main() {
     use jdk_pulse::{get_active_jdk, list_jdks, set_active_jdk, JdkInfo};
+    use jdk_pulse::doctor::{run_doctor_sync, DoctorReport};
+    use jdk_pulse::hooks::find_cli;
+    use jdk_pulse::integration::{install_shell_integration, remove_shell_integration, IntegrationChange};
     use jdk_pulse::tauri_tray::create_system_tray;
     use tauri::Manager;
 
@@ -22,6 +25,22 @@ fn main() {
         set_active_jdk(&id)
     }
 
+    /// The hooks must call the CLI, not this tray process.
+    #[tauri::command]
+    async fn install_shell_integration_command() -> Result<Vec<IntegrationChange>, String> {
+        install_shell_integration(&find_cli()?)
+    }
+
+    #[tauri::command]
+    async fn remove_shell_integration_command() -> Result<Vec<IntegrationChange>, String> {
+        remove_shell_integration()
+    }
+
+    #[tauri::command]
+    async fn run_doctor_sync_command() -> Result<DoctorReport, String> {
+        run_doctor_sync()
+    }
+
     tauri::Builder::default()
         .setup(|app| {
             // Create system tray and store it in app state
@@ -32,7 +51,10 @@ fn main() {
         .invoke_handler(tauri::generate_handler![
             list_jdks_command,
             get_active_jdk_command,
-            set_active_jdk_command
+            set_active_jdk_command,
+            install_shell_integration_command,
+            remove_shell_integration_command,
+            run_doctor_sync_command
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");